@@ -0,0 +1,815 @@
+/// How many times macros may be expanded before a macro that invokes itself is given up on.
+const MAX_EXPANSIONS: usize = 65536;
+/// The last address that `:org` may move to, the end of XO-CHIP's 64K of memory.
+const MAX_ADDRESS: i64 = 0xFFFF;
+
+/// Why a source file could not be assembled, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// An assembled ROM and the addresses of its labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// The bytes to load at the start address.
+    pub rom: Vec<u8>,
+    pub labels: std::collections::BTreeMap<String, usize>,
+}
+
+impl Program {
+    /// The labels as text, one `address name` pair per line in address order.
+    pub fn symbol_map(&self) -> String {
+        let mut symbols: Vec<(&usize, &String)> = self
+            .labels
+            .iter()
+            .map(|(name, address)| (address, name))
+            .collect();
+        symbols.sort();
+        symbols
+            .into_iter()
+            .map(|(address, name)| format!("{:04X} {}\n", address, name))
+            .collect()
+    }
+}
+
+/// Assembles Octo source into a ROM that is loaded at `start_address`.
+///
+/// Supports labels, `:const`, `:alias`, `:macro`, `:byte`, `:next`, `:org` and `:call`,
+/// `if ... then`, `loop ... while ... again` and the CHIP-8, SUPER-CHIP and XO-CHIP statements.
+pub fn assemble(source: &str, start_address: usize) -> Result<Program, AssembleError> {
+    let mut assembler = Assembler {
+        tokens: tokenize(source),
+        start_address,
+        address: start_address,
+        memory: Vec::new(),
+        assembled: Vec::new(),
+        labels: std::collections::BTreeMap::new(),
+        constants: std::collections::HashMap::new(),
+        aliases: std::collections::HashMap::new(),
+        macros: std::collections::HashMap::new(),
+        fixups: Vec::new(),
+        loops: Vec::new(),
+        expansions: 0,
+    };
+    while let Some(token) = assembler.tokens.pop_front() {
+        assembler.statement(token)?;
+    }
+    assembler.finish()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    text: String,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, message: impl Into<String>) -> AssembleError {
+        AssembleError {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+struct Macro {
+    arguments: Vec<String>,
+    body: Vec<Token>,
+}
+
+/// A loop that has not reached its `again` yet.
+struct Loop {
+    address: usize,
+    /// Where the jumps out of the loop that `while` assembles are.
+    exits: Vec<usize>,
+    token: Token,
+}
+
+/// An address operand that refers to a label that is defined later.
+struct Fixup {
+    /// Where the operand is assembled.
+    address: usize,
+    /// 12 for the low bits of an NNN opcode, 16 for the word after `i := long`.
+    bits: u32,
+    token: Token,
+}
+
+struct Assembler {
+    tokens: std::collections::VecDeque<Token>,
+    start_address: usize,
+    address: usize,
+    memory: Vec<u8>,
+    assembled: Vec<bool>,
+    labels: std::collections::BTreeMap<String, usize>,
+    constants: std::collections::HashMap<String, i64>,
+    aliases: std::collections::HashMap<String, u8>,
+    macros: std::collections::HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    loops: Vec<Loop>,
+    expansions: usize,
+}
+
+/// Splits source into whitespace-separated tokens, dropping `#` comments.
+fn tokenize(source: &str) -> std::collections::VecDeque<Token> {
+    let mut tokens = std::collections::VecDeque::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let mut current: Option<Token> = None;
+        for (column_index, character) in line.chars().enumerate() {
+            if character == '#' || character.is_whitespace() {
+                tokens.extend(current.take());
+                if character == '#' {
+                    break;
+                }
+                continue;
+            }
+            current
+                .get_or_insert_with(|| Token {
+                    text: String::new(),
+                    line: line_index + 1,
+                    column: column_index + 1,
+                })
+                .text
+                .push(character);
+        }
+        tokens.extend(current);
+    }
+    tokens
+}
+
+/// Reads decimal, `0x` hexadecimal and `0b` binary numbers, which may be negative.
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let magnitude = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()?
+    } else if digits.starts_with(|character: char| character.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// The instruction that skips when the instruction given skips, and the other way around.
+fn negate(skip: crate::instruction::Instruction) -> crate::instruction::Instruction {
+    use crate::instruction::Instruction;
+    match skip {
+        Instruction::SeByte { x, byte } => Instruction::SneByte { x, byte },
+        Instruction::SneByte { x, byte } => Instruction::SeByte { x, byte },
+        Instruction::SeReg { x, y } => Instruction::SneReg { x, y },
+        Instruction::SneReg { x, y } => Instruction::SeReg { x, y },
+        Instruction::Skp(x) => Instruction::Sknp(x),
+        Instruction::Sknp(x) => Instruction::Skp(x),
+        instruction => instruction,
+    }
+}
+
+impl Assembler {
+    fn statement(&mut self, token: Token) -> Result<(), AssembleError> {
+        use crate::instruction::Instruction;
+        let instruction = match token.text.as_str() {
+            ":" => {
+                let name = self.next(&token)?;
+                return self.define_label(&name, self.address);
+            }
+            ":next" => {
+                // labels the operand byte of the next instruction, for self-modifying code
+                let name = self.next(&token)?;
+                return self.define_label(&name, self.address + 1);
+            }
+            ":const" => {
+                let name = self.next(&token)?;
+                let value = self.next(&name)?;
+                let value = self.number(&value)?;
+                self.constants.insert(name.text, value);
+                return Ok(());
+            }
+            ":alias" => {
+                let name = self.next(&token)?;
+                let register = self.next(&name)?;
+                let register = self.register(&register)?;
+                self.aliases.insert(name.text, register);
+                return Ok(());
+            }
+            ":macro" => return self.define_macro(&token),
+            ":byte" => {
+                let value = self.next(&token)?;
+                let byte = self.byte(&value)?;
+                return self.emit(&[byte], &token);
+            }
+            ":org" => {
+                let value = self.next(&token)?;
+                let address = self.number(&value)?;
+                if address < self.start_address as i64 {
+                    return Err(value.error(format!(
+                        "{:#05X} is before the start address {:#05X}",
+                        address, self.start_address
+                    )));
+                }
+                if address > MAX_ADDRESS {
+                    return Err(value.error(format!(
+                        "{:#X} is past the end of memory at {:#X}",
+                        address, MAX_ADDRESS
+                    )));
+                }
+                self.address = address as usize;
+                return Ok(());
+            }
+            ":call" => Instruction::Call(self.next_address(&token, 12)?),
+            "clear" => Instruction::Cls,
+            "return" | ";" => Instruction::Ret,
+            "exit" => Instruction::Exit,
+            "lores" => Instruction::Low,
+            "hires" => Instruction::High,
+            "scroll-left" => Instruction::Scl,
+            "scroll-right" => Instruction::Scr,
+            "scroll-down" => Instruction::Scd(self.next_nibble(&token)?),
+            "scroll-up" => Instruction::Scu(self.next_nibble(&token)?),
+            "audio" => Instruction::Audio,
+            "plane" => Instruction::Plane(self.next_nibble(&token)?),
+            "jump" => Instruction::Jp(self.next_address(&token, 12)?),
+            "jump0" => Instruction::JpV0(self.next_address(&token, 12)?),
+            "sprite" => {
+                let x = self.next_register(&token)?;
+                let y = self.next_register(&token)?;
+                let n = self.next_nibble(&token)?;
+                Instruction::Drw { x, y, n }
+            }
+            "bcd" => Instruction::LdBcd(self.next_register(&token)?),
+            "saveflags" => Instruction::LdRVx(self.next_register(&token)?),
+            "loadflags" => Instruction::LdVxR(self.next_register(&token)?),
+            "save" | "load" => {
+                let x = self.next_register(&token)?;
+                let range = self.tokens.front().is_some_and(|next| next.text == "-");
+                let save = token.text == "save";
+                if range {
+                    self.tokens.pop_front();
+                    let y = self.next_register(&token)?;
+                    if save {
+                        Instruction::SaveRange { x, y }
+                    } else {
+                        Instruction::LoadRange { x, y }
+                    }
+                } else if save {
+                    Instruction::LdIVx(x)
+                } else {
+                    Instruction::LdVxI(x)
+                }
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(&token, ":=")?;
+                let x = self.next_register(&token)?;
+                match token.text.as_str() {
+                    "delay" => Instruction::LdDtVx(x),
+                    "buzzer" => Instruction::LdStVx(x),
+                    _ => Instruction::Pitch(x),
+                }
+            }
+            "i" => return self.index_statement(&token),
+            "if" => {
+                let skip = self.condition(&token)?;
+                self.expect(&token, "then")?;
+                // the next statement runs when the condition holds, so skip it when it does not
+                negate(skip)
+            }
+            "loop" => {
+                self.loops.push(Loop {
+                    address: self.address,
+                    exits: Vec::new(),
+                    token,
+                });
+                return Ok(());
+            }
+            "while" => {
+                if self.loops.is_empty() {
+                    return Err(token.error("while outside of a loop"));
+                }
+                let skip = self.condition(&token)?;
+                self.emit_instruction(skip, &token)?;
+                let exit = self.address;
+                self.loops.last_mut().unwrap().exits.push(exit);
+                Instruction::Jp(0)
+            }
+            "again" => {
+                let Some(finished) = self.loops.pop() else {
+                    return Err(token.error("again without a loop"));
+                };
+                let start = Self::check_address(finished.address as i64, 12, &finished.token)?;
+                self.emit_instruction(Instruction::Jp(start), &token)?;
+                for exit in finished.exits {
+                    self.patch(exit, 12, self.address, &finished.token)?;
+                }
+                return Ok(());
+            }
+            _ if self.is_register(&token.text) => {
+                let x = self.register(&token)?;
+                return self.assignment(x, &token);
+            }
+            _ if self.macros.contains_key(&token.text) => return self.expand(&token),
+            _ if self.is_number(&token.text) => {
+                let byte = self.byte(&token)?;
+                return self.emit(&[byte], &token);
+            }
+            // any other name calls the subroutine at that label
+            _ => Instruction::Call(self.address_operand(&token, 12)?),
+        };
+        self.emit_instruction(instruction, &token)
+    }
+
+    /// `i := NNN`, `i := long NNNN`, `i := hex vX`, `i := bighex vX` and `i += vX`.
+    fn index_statement(&mut self, token: &Token) -> Result<(), AssembleError> {
+        use crate::instruction::Instruction;
+        let operator = self.next(token)?;
+        let instruction = match operator.text.as_str() {
+            "+=" => Instruction::AddI(self.next_register(&operator)?),
+            ":=" => {
+                let operand = self.next(&operator)?;
+                match operand.text.as_str() {
+                    "hex" => Instruction::LdF(self.next_register(&operand)?),
+                    "bighex" => Instruction::LdHf(self.next_register(&operand)?),
+                    "long" => {
+                        // the address is the word after the opcode
+                        self.emit_instruction(Instruction::LdILong, token)?;
+                        let address = self.next_address(&operand, 16)?;
+                        return self.emit(&address.to_be_bytes(), token);
+                    }
+                    _ => Instruction::LdI(self.address_operand(&operand, 12)?),
+                }
+            }
+            _ => {
+                return Err(
+                    operator.error(format!("expected := or += after i, got {}", operator.text))
+                )
+            }
+        };
+        self.emit_instruction(instruction, token)
+    }
+
+    /// `vX op operand`, such as `v0 := 5`, `v0 += v1` or `v0 := random 0xFF`.
+    fn assignment(&mut self, x: u8, token: &Token) -> Result<(), AssembleError> {
+        use crate::instruction::Instruction;
+        let operator = self.next(token)?;
+        let operand = self.next(&operator)?;
+        let register = self
+            .is_register(&operand.text)
+            .then(|| self.register(&operand))
+            .transpose()?;
+        let instruction = match (operator.text.as_str(), register) {
+            (":=", Some(y)) => Instruction::LdReg { x, y },
+            (":=", None) => match operand.text.as_str() {
+                "random" => Instruction::Rnd {
+                    x,
+                    byte: self.next_byte(&operand)?,
+                },
+                "key" => Instruction::LdVxK(x),
+                "delay" => Instruction::LdVxDt(x),
+                _ => Instruction::LdByte {
+                    x,
+                    byte: self.byte(&operand)?,
+                },
+            },
+            ("+=", Some(y)) => Instruction::AddReg { x, y },
+            ("+=", None) => Instruction::AddByte {
+                x,
+                byte: self.byte(&operand)?,
+            },
+            ("-=", Some(y)) => Instruction::Sub { x, y },
+            ("-=", None) => Instruction::AddByte {
+                x,
+                byte: self.byte(&operand)?.wrapping_neg(),
+            },
+            ("=-", Some(y)) => Instruction::Subn { x, y },
+            ("|=", Some(y)) => Instruction::Or { x, y },
+            ("&=", Some(y)) => Instruction::And { x, y },
+            ("^=", Some(y)) => Instruction::Xor { x, y },
+            (">>=", Some(y)) => Instruction::Shr { x, y },
+            ("<<=", Some(y)) => Instruction::Shl { x, y },
+            ("=-" | "|=" | "&=" | "^=" | ">>=" | "<<=", None) => {
+                return Err(operand.error(format!("expected a register, got {}", operand.text)))
+            }
+            _ => {
+                return Err(operator.error(format!(
+                    "expected an operator after {}, got {}",
+                    token.text, operator.text
+                )))
+            }
+        };
+        self.emit_instruction(instruction, token)
+    }
+
+    /// Reads `vX == NN`, `vX != vY`, `vX key` or `vX -key`, returning the instruction that skips when it holds.
+    fn condition(
+        &mut self,
+        token: &Token,
+    ) -> Result<crate::instruction::Instruction, AssembleError> {
+        use crate::instruction::Instruction;
+        let x = self.next_register(token)?;
+        let operator = self.next(token)?;
+        match operator.text.as_str() {
+            "key" => Ok(Instruction::Skp(x)),
+            "-key" => Ok(Instruction::Sknp(x)),
+            "==" | "!=" => {
+                let operand = self.next(&operator)?;
+                let equal = operator.text == "==";
+                if self.is_register(&operand.text) {
+                    let y = self.register(&operand)?;
+                    Ok(if equal {
+                        Instruction::SeReg { x, y }
+                    } else {
+                        Instruction::SneReg { x, y }
+                    })
+                } else {
+                    let byte = self.byte(&operand)?;
+                    Ok(if equal {
+                        Instruction::SeByte { x, byte }
+                    } else {
+                        Instruction::SneByte { x, byte }
+                    })
+                }
+            }
+            _ => Err(operator.error(format!(
+                "expected ==, !=, key or -key, got {}",
+                operator.text
+            ))),
+        }
+    }
+
+    /// `:macro name arguments { body }`. Braces in the body must be balanced.
+    fn define_macro(&mut self, token: &Token) -> Result<(), AssembleError> {
+        let name = self.next(token)?;
+        let mut arguments = Vec::new();
+        loop {
+            let argument = self.next(&name)?;
+            if argument.text == "{" {
+                break;
+            }
+            arguments.push(argument.text);
+        }
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let body_token = self
+                .tokens
+                .pop_front()
+                .ok_or_else(|| name.error(format!("macro {} has no closing }}", name.text)))?;
+            match body_token.text.as_str() {
+                "{" => depth += 1,
+                "}" if depth == 0 => break,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            body.push(body_token);
+        }
+        self.macros.insert(name.text, Macro { arguments, body });
+        Ok(())
+    }
+
+    /// Replaces a macro's name and arguments with its body.
+    fn expand(&mut self, token: &Token) -> Result<(), AssembleError> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(token.error(format!("macro {} never stops expanding", token.text)));
+        }
+        let argument_count = self.macros[&token.text].arguments.len();
+        let mut values = Vec::new();
+        for _ in 0..argument_count {
+            values.push(self.next(token)?);
+        }
+        let definition = &self.macros[&token.text];
+        let expanded: Vec<Token> = definition
+            .body
+            .iter()
+            .map(|body_token| {
+                match definition
+                    .arguments
+                    .iter()
+                    .position(|argument| *argument == body_token.text)
+                {
+                    Some(index) => values[index].clone(),
+                    None => body_token.clone(),
+                }
+            })
+            .collect();
+        for body_token in expanded.into_iter().rev() {
+            self.tokens.push_front(body_token);
+        }
+        Ok(())
+    }
+
+    fn define_label(&mut self, name: &Token, address: usize) -> Result<(), AssembleError> {
+        if self.is_register(&name.text) || self.is_number(&name.text) {
+            return Err(name.error(format!("{} cannot be a label", name.text)));
+        }
+        if self.labels.contains_key(&name.text) {
+            return Err(name.error(format!("label {} is already defined", name.text)));
+        }
+        self.labels.insert(name.text.clone(), address);
+        Ok(())
+    }
+
+    fn next(&mut self, after: &Token) -> Result<Token, AssembleError> {
+        self.tokens
+            .pop_front()
+            .ok_or_else(|| after.error(format!("expected more after {}", after.text)))
+    }
+
+    fn expect(&mut self, after: &Token, text: &str) -> Result<(), AssembleError> {
+        let token = self.next(after)?;
+        if token.text == text {
+            Ok(())
+        } else {
+            Err(token.error(format!("expected {}, got {}", text, token.text)))
+        }
+    }
+
+    fn is_register(&self, text: &str) -> bool {
+        let bytes = text.as_bytes();
+        let named = bytes.len() == 2
+            && bytes[0].eq_ignore_ascii_case(&b'v')
+            && bytes[1].is_ascii_hexdigit();
+        named || self.aliases.contains_key(text)
+    }
+
+    fn is_number(&self, text: &str) -> bool {
+        parse_number(text).is_some() || self.constants.contains_key(text)
+    }
+
+    fn register(&self, token: &Token) -> Result<u8, AssembleError> {
+        if let Some(register) = self.aliases.get(&token.text) {
+            return Ok(*register);
+        }
+        match token.text.as_bytes() {
+            [v, digit] if v.eq_ignore_ascii_case(&b'v') && digit.is_ascii_hexdigit() => {
+                Ok((*digit as char).to_digit(16).unwrap() as u8)
+            }
+            _ => Err(token.error(format!("expected a register, got {}", token.text))),
+        }
+    }
+
+    fn number(&self, token: &Token) -> Result<i64, AssembleError> {
+        parse_number(&token.text)
+            .or_else(|| self.constants.get(&token.text).copied())
+            .ok_or_else(|| token.error(format!("expected a number, got {}", token.text)))
+    }
+
+    fn byte(&self, token: &Token) -> Result<u8, AssembleError> {
+        match self.number(token)? {
+            value @ -128..=255 => Ok(value as u8),
+            value => Err(token.error(format!("{} does not fit in a byte", value))),
+        }
+    }
+
+    fn next_register(&mut self, after: &Token) -> Result<u8, AssembleError> {
+        let token = self.next(after)?;
+        self.register(&token)
+    }
+
+    fn next_byte(&mut self, after: &Token) -> Result<u8, AssembleError> {
+        let token = self.next(after)?;
+        self.byte(&token)
+    }
+
+    fn next_nibble(&mut self, after: &Token) -> Result<u8, AssembleError> {
+        let token = self.next(after)?;
+        match self.number(&token)? {
+            value @ 0..=15 => Ok(value as u8),
+            value => Err(token.error(format!("{} does not fit in a nibble", value))),
+        }
+    }
+
+    fn next_address(&mut self, after: &Token, bits: u32) -> Result<u16, AssembleError> {
+        let token = self.next(after)?;
+        self.address_operand(&token, bits)
+    }
+
+    /// The address a number, constant or label stands for. Labels that are not defined yet
+    /// are filled in by `finish`, so they assemble as 0 for now.
+    fn address_operand(&mut self, token: &Token, bits: u32) -> Result<u16, AssembleError> {
+        let address = match (self.is_number(&token.text), self.labels.get(&token.text)) {
+            (true, _) => self.number(token)?,
+            (false, Some(address)) => *address as i64,
+            (false, None) => {
+                self.fixups.push(Fixup {
+                    address: self.address,
+                    bits,
+                    token: token.clone(),
+                });
+                return Ok(0);
+            }
+        };
+        Self::check_address(address, bits, token)
+    }
+
+    /// Returns the address if it fits in an operand of `bits` bits.
+    fn check_address(address: i64, bits: u32, token: &Token) -> Result<u16, AssembleError> {
+        if !(0..1 << bits).contains(&address) {
+            return Err(token.error(format!(
+                "address {:#X} does not fit in {} bits",
+                address, bits
+            )));
+        }
+        Ok(address as u16)
+    }
+
+    fn emit_instruction(
+        &mut self,
+        instruction: crate::instruction::Instruction,
+        token: &Token,
+    ) -> Result<(), AssembleError> {
+        self.emit(
+            &crate::instruction::encode(&instruction).to_be_bytes(),
+            token,
+        )
+    }
+
+    fn emit(&mut self, bytes: &[u8], token: &Token) -> Result<(), AssembleError> {
+        let offset = self.address - self.start_address;
+        if self.memory.len() < offset + bytes.len() {
+            self.memory.resize(offset + bytes.len(), 0);
+            self.assembled.resize(offset + bytes.len(), false);
+        }
+        if self.assembled[offset..offset + bytes.len()].contains(&true) {
+            return Err(token.error(format!("{:#05X} has already been assembled", self.address)));
+        }
+        self.memory[offset..offset + bytes.len()].clone_from_slice(bytes);
+        self.assembled[offset..offset + bytes.len()].fill(true);
+        self.address += bytes.len();
+        Ok(())
+    }
+
+    /// Writes an address into the operand at `address`.
+    fn patch(
+        &mut self,
+        address: usize,
+        bits: u32,
+        value: usize,
+        token: &Token,
+    ) -> Result<(), AssembleError> {
+        let value = Self::check_address(value as i64, bits, token)?;
+        let offset = address - self.start_address;
+        let [high, low] = value.to_be_bytes();
+        if bits == 12 {
+            self.memory[offset] = (self.memory[offset] & 0xF0) | high;
+        } else {
+            self.memory[offset] = high;
+        }
+        self.memory[offset + 1] = low;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Program, AssembleError> {
+        if let Some(unfinished) = self.loops.last() {
+            return Err(unfinished.token.error("loop without again"));
+        }
+        for fixup in std::mem::take(&mut self.fixups) {
+            let address = *self.labels.get(&fixup.token.text).ok_or_else(|| {
+                fixup
+                    .token
+                    .error(format!("undefined label {}", fixup.token.text))
+            })?;
+            self.patch(fixup.address, fixup.bits, address, &fixup.token)?;
+        }
+        Ok(Program {
+            rom: self.memory,
+            labels: self.labels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod assembler_tests {
+    use super::{assemble, AssembleError};
+    use crate::emulator::Emulator;
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+            :const SPEED 3
+            :alias x v0
+            : main
+                x := 0
+                i := dot # labels can be used before they are defined
+                loop
+                    sprite x v1 1
+                    x += SPEED
+                    while x != 12
+                again
+                if v1 == 0 then jump main
+            : halt
+                jump halt
+            : dot
+                :byte 0x80
+        ";
+        let program = assemble(source, 0x200).unwrap();
+        assert_eq!(
+            program.rom,
+            [
+                0x60, 0x00, 0xA2, 0x14, 0xD0, 0x11, 0x70, 0x03, 0x40, 0x0C, 0x12, 0x0E, 0x12, 0x04,
+                0x41, 0x00, 0x12, 0x00, 0x12, 0x12, 0x80
+            ]
+        );
+        assert_eq!(program.labels["dot"], 0x214);
+    }
+
+    #[test]
+    fn test_macros_next_and_org() {
+        let source = "
+            :macro set-both value {
+                v0 := value
+                v1 := value
+            }
+            set-both 7
+            :next target
+            v2 := 0
+            i := long far
+            :org 0x300
+            : far
+            :byte 1
+        ";
+        let program = assemble(source, 0x200).unwrap();
+        assert_eq!(
+            program.rom[..10],
+            [0x60, 0x07, 0x61, 0x07, 0x62, 0x00, 0xF0, 0x00, 0x03, 0x00]
+        );
+        assert_eq!(program.rom.len(), 0x101);
+        assert_eq!(program.rom[0x100], 1);
+        assert_eq!(program.symbol_map(), "0205 target\n0300 far\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source: &str| assemble(source, 0x200).unwrap_err();
+        let at = |line, column, message: &str| AssembleError {
+            line,
+            column,
+            message: message.to_string(),
+        };
+        assert_eq!(error("v0 := 256"), at(1, 7, "256 does not fit in a byte"));
+        assert_eq!(
+            error("clear\n  jump nowhere"),
+            at(2, 8, "undefined label nowhere")
+        );
+        assert_eq!(error("loop\n  v0 += 1"), at(1, 1, "loop without again"));
+        assert_eq!(error(": a\n: a"), at(2, 3, "label a is already defined"));
+        assert_eq!(
+            error("v0 ?= v1"),
+            at(1, 4, "expected an operator after v0, got ?=")
+        );
+        assert_eq!(
+            error("sprite v0 v1"),
+            at(1, 1, "expected more after sprite")
+        );
+        assert_eq!(
+            error("jump 0x1000"),
+            at(1, 6, "address 0x1000 does not fit in 12 bits")
+        );
+        assert_eq!(
+            error(":macro forever { forever }\nforever"),
+            at(1, 18, "macro forever never stops expanding")
+        );
+        assert_eq!(
+            error("v0 := 1\n:org 0x200\nv1 := 1"),
+            at(3, 1, "0x200 has already been assembled")
+        );
+        assert_eq!(
+            error(":org 0x1000\nloop\n  v0 += 1\nagain"),
+            at(2, 1, "address 0x1000 does not fit in 12 bits")
+        );
+        assert_eq!(
+            error(":org 0xFFFFFFFFFF\n:byte 1"),
+            at(1, 6, "0xFFFFFFFFFF is past the end of memory at 0xFFFF")
+        );
+        assert_eq!(
+            error("a0 := 1 # not a register\n").to_string(),
+            "1:1: undefined label a0"
+        );
+    }
+
+    #[test]
+    fn test_loads_in_emulator() {
+        let mut emulator = Emulator::assembled("i := hex v0 sprite v0 v0 5 : end jump end");
+        for _ in 0..3 {
+            emulator.step().unwrap();
+        }
+        // the top row of the font's 0 is 1111
+        assert!(emulator.framebuffer().get(3, 0));
+        assert!(!emulator.framebuffer().get(4, 0));
+    }
+}
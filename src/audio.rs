@@ -0,0 +1,221 @@
+/// Where the buzzer goes. The emulator reports the buzzer's state to its sink once per frame.
+pub trait AudioSink {
+    /// Called at the end of every frame with the frame's number and whether the sound timer is running.
+    fn set_buzzer(&mut self, frame: u64, on: bool);
+    /// Called when an XO-CHIP ROM loads an audio pattern or changes its pitch, and when a save state is loaded.
+    /// The buzzer plays its regular tone while there is no pattern.
+    fn set_pattern(&mut self, frame: u64, pattern: Option<crate::buzzer::AudioPattern>);
+    /// Called when a MEGA-CHIP ROM starts a digitized sound with 060N, or stops it with 0700.
+    /// A new sound replaces the one playing.
+    fn play_sample(&mut self, frame: u64, sample: Option<crate::megachip::Sample>);
+}
+
+/// Plays the buzzer through the default output device.
+pub struct RodioSink {
+    // the stream stops playing when dropped, so it lives as long as the sink
+    _stream: rodio::OutputStream,
+    handle: rodio::OutputStreamHandle,
+    _sink: rodio::Sink,
+    sample_sink: Option<rodio::Sink>,
+    active: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    pattern: std::sync::Arc<std::sync::Mutex<Option<crate::buzzer::AudioPattern>>>,
+}
+
+impl RodioSink {
+    /// Opens the default output device and starts the buzzer's waveform, which stays silent until turned on.
+    pub fn new(settings: crate::buzzer::BuzzerSettings) -> Result<Self, crate::error::Chip8Error> {
+        let unavailable =
+            |error: &dyn std::fmt::Display| crate::error::Chip8Error::AudioUnavailable {
+                reason: error.to_string(),
+            };
+        let (stream, handle) = rodio::OutputStream::try_default().map_err(|e| unavailable(&e))?;
+        let sink = rodio::Sink::try_new(&handle).map_err(|e| unavailable(&e))?;
+        let active = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let pattern = std::sync::Arc::new(std::sync::Mutex::new(None));
+        sink.append(crate::buzzer::BuzzerSource::new(
+            settings,
+            active.clone(),
+            pattern.clone(),
+        ));
+        Ok(Self {
+            _stream: stream,
+            handle,
+            _sink: sink,
+            sample_sink: None,
+            active,
+            pattern,
+        })
+    }
+}
+
+impl AudioSink for RodioSink {
+    fn set_buzzer(&mut self, _frame: u64, on: bool) {
+        self.active.store(on, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn set_pattern(&mut self, _frame: u64, pattern: Option<crate::buzzer::AudioPattern>) {
+        *self
+            .pattern
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = pattern;
+    }
+
+    fn play_sample(&mut self, _frame: u64, sample: Option<crate::megachip::Sample>) {
+        if let Some(sink) = self.sample_sink.take() {
+            sink.stop();
+        }
+        let Some(sample) = sample else {
+            return;
+        };
+        let sink = match rodio::Sink::try_new(&self.handle) {
+            Ok(sink) => sink,
+            Err(error) => {
+                log::warn!("could not play sample: {}", error);
+                return;
+            }
+        };
+        // unsigned 8-bit samples are centred on 128
+        let data: Vec<f32> = sample
+            .data
+            .iter()
+            .map(|byte| (*byte as f32 - 128.0) / 128.0)
+            .collect();
+        let source = rodio::buffer::SamplesBuffer::new(1, sample.sample_rate.max(1) as u32, data);
+        if sample.looping {
+            sink.append(rodio::Source::repeat_infinite(source));
+        } else {
+            sink.append(source);
+        }
+        self.sample_sink = Some(sink);
+    }
+}
+
+/// Discards the buzzer. Used when sound is muted or there is no output device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn set_buzzer(&mut self, _frame: u64, _on: bool) {}
+
+    fn set_pattern(&mut self, _frame: u64, _pattern: Option<crate::buzzer::AudioPattern>) {}
+
+    fn play_sample(&mut self, _frame: u64, _sample: Option<crate::megachip::Sample>) {}
+}
+
+/// The buzzer turning on or off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuzzerTransition {
+    /// The frame at the end of which the buzzer changed.
+    pub frame: u64,
+    pub on: bool,
+}
+
+/// A new XO-CHIP audio pattern or pitch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternChange {
+    /// The frame during which the pattern changed.
+    pub frame: u64,
+    pub pattern: Option<crate::buzzer::AudioPattern>,
+}
+
+/// A MEGA-CHIP sound starting or stopping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleChange {
+    /// The frame during which the sound started or stopped.
+    pub frame: u64,
+    pub sample: Option<crate::megachip::Sample>,
+}
+
+/// Records when the buzzer turns on and off, when its pattern changes and which sounds are played. Clones share the same recording,
+/// so one can be handed to the emulator while another is kept to inspect it.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureSink {
+    transitions: std::rc::Rc<std::cell::RefCell<Vec<BuzzerTransition>>>,
+    patterns: std::rc::Rc<std::cell::RefCell<Vec<PatternChange>>>,
+    samples: std::rc::Rc<std::cell::RefCell<Vec<SampleChange>>>,
+}
+
+impl CaptureSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn transitions(&self) -> Vec<BuzzerTransition> {
+        self.transitions.borrow().clone()
+    }
+
+    pub fn patterns(&self) -> Vec<PatternChange> {
+        self.patterns.borrow().clone()
+    }
+
+    pub fn samples(&self) -> Vec<SampleChange> {
+        self.samples.borrow().clone()
+    }
+}
+
+impl AudioSink for CaptureSink {
+    fn set_buzzer(&mut self, frame: u64, on: bool) {
+        let mut transitions = self.transitions.borrow_mut();
+        let was_on = transitions.last().is_some_and(|transition| transition.on);
+        if on != was_on {
+            transitions.push(BuzzerTransition { frame, on });
+        }
+    }
+
+    fn set_pattern(&mut self, frame: u64, pattern: Option<crate::buzzer::AudioPattern>) {
+        self.patterns
+            .borrow_mut()
+            .push(PatternChange { frame, pattern });
+    }
+
+    fn play_sample(&mut self, frame: u64, sample: Option<crate::megachip::Sample>) {
+        self.samples
+            .borrow_mut()
+            .push(SampleChange { frame, sample });
+    }
+}
+
+/// Plays through the default output device if sound is enabled and one is available, otherwise discards the buzzer.
+pub fn output_or_null(
+    enable_sound: bool,
+    settings: crate::buzzer::BuzzerSettings,
+) -> Box<dyn AudioSink> {
+    if !enable_sound {
+        return Box::new(NullSink);
+    }
+    match RodioSink::new(settings) {
+        Ok(sink) => Box::new(sink),
+        Err(error) => {
+            log::warn!("{}, continuing without sound", error);
+            Box::new(NullSink)
+        }
+    }
+}
+
+#[cfg(test)]
+mod audio_tests {
+    use super::{AudioSink, BuzzerTransition, CaptureSink};
+
+    #[test]
+    fn test_capture_records_transitions() {
+        let capture = CaptureSink::new();
+        let mut sink = capture.clone();
+        for (frame, on) in [false, true, true, false, false, true]
+            .into_iter()
+            .enumerate()
+        {
+            sink.set_buzzer(frame as u64, on);
+        }
+        assert_eq!(
+            capture.transitions(),
+            vec![
+                BuzzerTransition { frame: 1, on: true },
+                BuzzerTransition {
+                    frame: 3,
+                    on: false
+                },
+                BuzzerTransition { frame: 5, on: true },
+            ]
+        );
+    }
+}
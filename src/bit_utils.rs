@@ -1,7 +1,7 @@
 pub fn append_number_bits(nums: &[u8; 2]) -> u16 {
     let [left_num, right_num]: [u8; 2] = *nums;
     let mut result: u16 = bit_range_to_num(left_num.into(), 0, 8).unwrap() << 8;
-    result = result | bit_range_to_num(right_num.into(), 0, 8).unwrap();
+    result |= bit_range_to_num(right_num.into(), 0, 8).unwrap();
     result
 }
 
@@ -15,7 +15,7 @@ pub fn bit_range_to_num(num: u16, start: usize, end: usize) -> Option<u16> {
     let mut result = 0;
     for i in start..end {
         let ith_bit = num & (1 << i);
-        result = result | ith_bit;
+        result |= ith_bit;
     }
     Some(result >> start)
 }
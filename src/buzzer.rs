@@ -0,0 +1,249 @@
+/// How long the tone takes to fade in or out, which avoids clicks when the buzzer starts and stops.
+const RAMP_SECONDS: f32 = 0.005;
+const SAMPLE_RATE: u32 = 44_100;
+/// How many samples play between looks at the shared pattern, so the audio thread locks it 100 times a second.
+const PATTERN_REFRESH_SAMPLES: u32 = SAMPLE_RATE / 100;
+
+/// The tone played while the sound timer is non-zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuzzerSettings {
+    /// Pitch of the square wave in Hz.
+    pub frequency: f32,
+    /// Loudness from 0.0 (silent) to 1.0 (full scale).
+    pub volume: f32,
+}
+
+impl Default for BuzzerSettings {
+    fn default() -> Self {
+        Self {
+            frequency: 440.0,
+            volume: 0.25,
+        }
+    }
+}
+
+/// An XO-CHIP audio pattern: 128 1-bit samples, loaded by F002 and played in a loop at the rate set by FX3A.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioPattern {
+    pub pattern: [u8; 16],
+    pub pitch: u8,
+}
+
+impl AudioPattern {
+    /// How many of the pattern's bits play per second: 4000 * 2^((pitch - 64) / 48).
+    pub fn playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// Whether the bit at `index` is set, starting from the most significant bit of the first byte.
+    pub fn bit(&self, index: usize) -> bool {
+        self.pattern[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+}
+
+/// The buzzer's endless waveform, which is audible only while its switch is on.
+/// It plays a square wave unless an XO-CHIP audio pattern has been loaded.
+/// The switch and pattern are shared with the emulator loop, which changes them as the ROM runs.
+pub struct BuzzerSource {
+    settings: BuzzerSettings,
+    sample_rate: u32,
+    phase: f32,
+    gain: f32,
+    ramp_step: f32,
+    active: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    pattern: std::sync::Arc<std::sync::Mutex<Option<AudioPattern>>>,
+    /// The pattern as of the last look, which plays until the next.
+    current_pattern: Option<AudioPattern>,
+    samples_until_refresh: u32,
+}
+
+impl BuzzerSource {
+    pub fn new(
+        settings: BuzzerSettings,
+        active: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        pattern: std::sync::Arc<std::sync::Mutex<Option<AudioPattern>>>,
+    ) -> Self {
+        Self {
+            settings,
+            sample_rate: SAMPLE_RATE,
+            phase: 0.0,
+            gain: 0.0,
+            ramp_step: 1.0 / (RAMP_SECONDS * SAMPLE_RATE as f32),
+            active,
+            pattern,
+            current_pattern: None,
+            samples_until_refresh: 0,
+        }
+    }
+}
+
+impl Iterator for BuzzerSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let target = if self.active.load(std::sync::atomic::Ordering::Relaxed) {
+            1.0
+        } else {
+            0.0
+        };
+        if self.gain < target {
+            self.gain = (self.gain + self.ramp_step).min(target);
+        } else if self.gain > target {
+            self.gain = (self.gain - self.ramp_step).max(target);
+        }
+
+        if self.samples_until_refresh == 0 {
+            self.samples_until_refresh = PATTERN_REFRESH_SAMPLES;
+            // keep the old pattern rather than wait while the emulator loop holds the lock
+            match self.pattern.try_lock() {
+                Ok(pattern) => self.current_pattern = *pattern,
+                Err(std::sync::TryLockError::Poisoned(poisoned)) => {
+                    self.current_pattern = *poisoned.into_inner()
+                }
+                Err(std::sync::TryLockError::WouldBlock) => {}
+            }
+        }
+        self.samples_until_refresh -= 1;
+
+        // the phase counts square wave periods, or bits of the pattern
+        let high = match self.current_pattern {
+            Some(pattern) => {
+                let high = pattern.bit(self.phase as usize);
+                self.phase =
+                    (self.phase + pattern.playback_rate() / self.sample_rate as f32) % 128.0;
+                high
+            }
+            None => {
+                let high = self.phase.fract() < 0.5;
+                self.phase =
+                    (self.phase + self.settings.frequency / self.sample_rate as f32).fract();
+                high
+            }
+        };
+        let level = if high { 1.0 } else { -1.0 };
+
+        Some(level * self.settings.volume * self.gain)
+    }
+}
+
+impl rodio::Source for BuzzerSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod buzzer_tests {
+    use super::{AudioPattern, BuzzerSettings, BuzzerSource};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_silent_while_inactive() {
+        let active = Arc::new(AtomicBool::new(false));
+        let mut wave = BuzzerSource::new(
+            BuzzerSettings::default(),
+            active,
+            Arc::new(Mutex::new(None)),
+        );
+        assert!((0..1000).all(|_| wave.next() == Some(0.0)));
+    }
+
+    #[test]
+    fn test_ramps_in_and_out() {
+        let settings = BuzzerSettings {
+            frequency: 441.0,
+            volume: 0.5,
+        };
+        let active = Arc::new(AtomicBool::new(true));
+        let mut wave = BuzzerSource::new(settings, active.clone(), Arc::new(Mutex::new(None)));
+
+        // the first sample is quieter than full volume because of the ramp
+        let first = wave.next().unwrap();
+        assert!(first > 0.0 && first < 0.5);
+
+        // after the ramp, samples alternate between full positive and negative volume, 100 samples per period
+        let samples: Vec<f32> = (0..1000).map(|_| wave.next().unwrap()).collect();
+        assert!(samples[500..].iter().all(|sample| sample.abs() == 0.5));
+        assert!(samples[500..].iter().any(|sample| *sample < 0.0));
+
+        active.store(false, Ordering::Relaxed);
+        let samples: Vec<f32> = (0..1000).map(|_| wave.next().unwrap()).collect();
+        assert!(samples[0].abs() > 0.0);
+        assert!(samples[500..].iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn test_playback_rate() {
+        let mut pattern = AudioPattern {
+            pattern: [0; 16],
+            pitch: 64,
+        };
+        assert_eq!(pattern.playback_rate(), 4000.0);
+        pattern.pitch = 112;
+        assert_eq!(pattern.playback_rate(), 8000.0);
+        pattern.pitch = 16;
+        assert_eq!(pattern.playback_rate(), 2000.0);
+    }
+
+    #[test]
+    fn test_plays_pattern() {
+        // the first half of the pattern is high, the second half low
+        let pattern = AudioPattern {
+            pattern: [[0xFF; 8], [0x00; 8]].concat().try_into().unwrap(),
+            pitch: 64,
+        };
+        let settings = BuzzerSettings {
+            frequency: 441.0,
+            volume: 1.0,
+        };
+        let mut wave = BuzzerSource::new(
+            settings,
+            Arc::new(AtomicBool::new(true)),
+            Arc::new(Mutex::new(Some(pattern))),
+        );
+        // at 4000 bits a second, the 128-bit pattern repeats every 1411.2 samples
+        let samples: Vec<f32> = (0..1411).map(|_| wave.next().unwrap()).collect();
+        assert!(samples[300..700].iter().all(|sample| *sample == 1.0));
+        assert!(samples[720..1400].iter().all(|sample| *sample == -1.0));
+    }
+
+    #[test]
+    fn test_picks_up_pattern_changes() {
+        let pattern = Arc::new(Mutex::new(None));
+        let settings = BuzzerSettings {
+            frequency: 441.0,
+            volume: 1.0,
+        };
+        let mut wave =
+            BuzzerSource::new(settings, Arc::new(AtomicBool::new(true)), pattern.clone());
+        wave.nth(1000);
+
+        // a pattern of low bits takes over from the square wave at the next refresh
+        *pattern.lock().unwrap() = Some(AudioPattern {
+            pattern: [0; 16],
+            pitch: 64,
+        });
+        let samples: Vec<f32> = (0..super::PATTERN_REFRESH_SAMPLES * 2)
+            .map(|_| wave.next().unwrap())
+            .collect();
+        assert!(samples[..super::PATTERN_REFRESH_SAMPLES as usize / 2]
+            .iter()
+            .any(|sample| *sample > 0.0));
+        assert!(samples[super::PATTERN_REFRESH_SAMPLES as usize..]
+            .iter()
+            .all(|sample| *sample == -1.0));
+    }
+}
@@ -0,0 +1,101 @@
+/// The VP-590 color board's foreground colors, selected by BXYN.
+pub const FOREGROUND_COLORS: [[u8; 3]; 8] = [
+    [0x00, 0x00, 0x00],
+    [0xFF, 0x00, 0x00],
+    [0x00, 0x00, 0xFF],
+    [0xFF, 0x00, 0xFF],
+    [0x00, 0xFF, 0x00],
+    [0xFF, 0xFF, 0x00],
+    [0x00, 0xFF, 0xFF],
+    [0xFF, 0xFF, 0xFF],
+];
+/// The background colors that 02A0 cycles through.
+pub const BACKGROUND_COLORS: [[u8; 3]; 4] = [
+    [0x00, 0x00, 0x80],
+    [0x00, 0x00, 0x00],
+    [0x00, 0x80, 0x00],
+    [0x80, 0x00, 0x00],
+];
+/// Colors are stored for columns of 8 pixels.
+pub const ZONE_WIDTH: usize = 8;
+/// BXY0 colors zones 4 pixels high.
+pub const ZONE_HEIGHT: usize = 4;
+/// The foreground color of every zone after a reset.
+const DEFAULT_FOREGROUND: u8 = 1;
+
+/// CHIP-8X's color memory: one background color for the whole screen,
+/// and a foreground color for each row of each 8-pixel column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorZones {
+    background: usize,
+    columns: usize,
+    rows: usize,
+    foreground: Vec<u8>,
+}
+
+impl ColorZones {
+    pub fn new(width: usize, height: usize) -> Self {
+        let columns = width.div_ceil(ZONE_WIDTH);
+        Self {
+            background: 0,
+            columns,
+            rows: height,
+            foreground: vec![DEFAULT_FOREGROUND; columns * height],
+        }
+    }
+
+    /// Moves on to the next background color, wrapping around after the last.
+    pub fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % BACKGROUND_COLORS.len();
+    }
+
+    /// Sets the foreground color of the columns and rows in the ranges, ignoring any that are off the screen.
+    pub fn set_foreground(
+        &mut self,
+        columns: std::ops::Range<usize>,
+        rows: std::ops::Range<usize>,
+        color: u8,
+    ) {
+        for row in rows.start.min(self.rows)..rows.end.min(self.rows) {
+            for column in columns.start.min(self.columns)..columns.end.min(self.columns) {
+                self.foreground[row * self.columns + column] =
+                    color % FOREGROUND_COLORS.len() as u8;
+            }
+        }
+    }
+
+    /// The color shown at (x, y): the zone's foreground color if the pixel is lit, otherwise the background color.
+    pub fn color(&self, x: usize, y: usize, lit: bool) -> [u8; 3] {
+        if lit {
+            FOREGROUND_COLORS[self.foreground[y * self.columns + x / ZONE_WIDTH] as usize]
+        } else {
+            BACKGROUND_COLORS[self.background]
+        }
+    }
+}
+
+#[cfg(test)]
+mod chip8x_tests {
+    use super::{ColorZones, BACKGROUND_COLORS, FOREGROUND_COLORS};
+
+    #[test]
+    fn test_color_zones() {
+        let mut zones = ColorZones::new(64, 32);
+        assert_eq!(zones.color(0, 0, false), BACKGROUND_COLORS[0]);
+        assert_eq!(zones.color(0, 0, true), FOREGROUND_COLORS[1]);
+
+        zones.set_foreground(1..3, 4..8, 6);
+        assert_eq!(zones.color(8, 4, true), FOREGROUND_COLORS[6]);
+        assert_eq!(zones.color(23, 7, true), FOREGROUND_COLORS[6]);
+        assert_eq!(zones.color(24, 7, true), FOREGROUND_COLORS[1]);
+        assert_eq!(zones.color(8, 8, true), FOREGROUND_COLORS[1]);
+        // zones off the screen are ignored
+        zones.set_foreground(7..20, 30..40, 2);
+        assert_eq!(zones.color(63, 31, true), FOREGROUND_COLORS[2]);
+
+        for _ in 0..5 {
+            zones.cycle_background();
+        }
+        assert_eq!(zones.color(0, 0, false), BACKGROUND_COLORS[1]);
+    }
+}
@@ -0,0 +1,591 @@
+/// How to use the binary, printed by --help and after argument errors.
+pub const USAGE: &str = "\
+usage: chip8 [OPTIONS] ROM
+       chip8 disasm [--platform NAME] [--syntax octo|cowgod] ROM
+       chip8 asm [--platform NAME] [-o ROM] [--symbols FILE] SOURCE
+       chip8 debug [OPTIONS] ROM
+       chip8 gdb [--port N] [OPTIONS] ROM
+
+options:
+  --platform NAME         vip, vip-hires, chip8x, eti660, dream6800, chip48, schip, xochip or megachip
+                          (default: schip with the schip-modern quirks)
+  --quirks PRESET         vip, chip48, schip1.0, schip1.1, schip-modern or xochip
+  --quirk NAME=VALUE      vf-reset, shift-uses-vy, jump-with-vx, clip-sprites, display-wait,
+                          key-on-release or count-collided-rows with on or off,
+                          or memory-increment with none, x or x+1
+  --ipf N                 instructions per frame
+  --scale N               window pixels per CHIP-8 pixel (default: 10)
+  --palette COLORS        comma-separated hex colors for the background, the first plane,
+                          the second plane and both planes, e.g. 000000,ffffff
+  --mute                  no sound
+  --seed N                seed for the random number generator
+  --paused                start paused, space pauses and resumes
+  --flags-dir DIR         where FX75 saves the RPL user flags
+                          (default: $XDG_DATA_HOME/chip8/flags or ~/.local/share/chip8/flags)
+  -h, --help              show this message
+
+disasm prints a listing of the ROM, in Octo syntax unless --syntax cowgod is given.
+asm assembles Octo source into SOURCE with a .ch8 extension, or the ROM given by -o,
+and writes the address of each label to the --symbols file.
+debug steps through the ROM in the terminal, taking the same options as playing it.
+gdb waits for gdb or lldb on 127.0.0.1, port 1234 unless --port is given, with the same options.";
+
+/// What the binary was asked to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Play a ROM in a window.
+    Run(RunOptions),
+    /// Print a listing of a ROM.
+    Disasm(DisasmOptions),
+    /// Assemble a ROM from source.
+    Asm(AsmOptions),
+    /// Step through a ROM in the terminal.
+    Debug(RunOptions),
+    /// Debug a ROM from gdb or lldb.
+    Gdb(GdbOptions),
+    /// Print the usage.
+    Help,
+}
+
+/// Everything needed to play a ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOptions {
+    pub rom: String,
+    pub config: crate::config::EmulatorConfig,
+    pub frontend: crate::frontend::FrontendOptions,
+    pub mute: bool,
+    /// Where the RPL user flags are saved, or None for the default directory.
+    pub flags_dir: Option<String>,
+}
+
+/// Everything needed to serve a ROM to gdb.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdbOptions {
+    pub run: RunOptions,
+    pub port: u16,
+}
+
+/// Everything needed to disassemble a ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisasmOptions {
+    pub rom: String,
+    pub platform: crate::platform::Platform,
+    pub syntax: crate::disassembler::Syntax,
+}
+
+/// Everything needed to assemble a ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmOptions {
+    pub source: String,
+    pub output: String,
+    pub symbols: Option<String>,
+    pub platform: crate::platform::Platform,
+}
+
+/// Parses the arguments that follow the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, crate::error::Chip8Error> {
+    let mut args = args.into_iter().peekable();
+    if args.next_if(|arg| arg == "disasm").is_some() {
+        return parse_disasm(args);
+    }
+    if args.next_if(|arg| arg == "asm").is_some() {
+        return parse_asm(args);
+    }
+    let mode = args.next_if(|arg| arg == "debug" || arg == "gdb");
+    let mut port = crate::gdb::DEFAULT_PORT;
+    let mut rom = None;
+    let mut platform = None;
+    let mut quirks = None;
+    let mut quirk_overrides = Vec::new();
+    let mut instructions_per_frame = None;
+    let mut rng_seed = None;
+    let mut frontend = crate::frontend::FrontendOptions::default();
+    let mut mute = false;
+    let mut flags_dir = None;
+
+    while let Some(arg) = args.next() {
+        let (option, inline_value) = split_option(&arg);
+        let mut value = || option_value(&option, &inline_value, &mut args);
+        match option.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--platform" => platform = Some(parse_platform(&option, &value()?)?),
+            "--quirks" => {
+                let name = value()?;
+                quirks = Some(
+                    crate::quirks::Quirks::from_name(&name)
+                        .ok_or_else(|| invalid(&option, &format!("unknown preset {}", name)))?,
+                );
+            }
+            "--quirk" => {
+                let setting = value()?;
+                let (name, quirk_value) = setting
+                    .split_once('=')
+                    .ok_or_else(|| invalid(&option, "expected NAME=VALUE"))?;
+                quirk_overrides.push((name.to_string(), quirk_value.to_string()));
+            }
+            "--ipf" | "--instructions-per-frame" => {
+                instructions_per_frame = Some(parse_positive(&option, &value()?)?)
+            }
+            "--scale" => frontend.scale = parse_positive(&option, &value()?)? as u32,
+            "--palette" => frontend.palette = parse_palette(&option, &value()?)?,
+            "--mute" => mute = true,
+            "--seed" => {
+                let seed = value()?;
+                rng_seed = Some(seed.parse().map_err(|_| {
+                    invalid(&option, &format!("expected a whole number, got {}", seed))
+                })?);
+            }
+            "--paused" => frontend.paused = true,
+            "--flags-dir" => flags_dir = Some(value()?),
+            "--port" if mode.as_deref() == Some("gdb") => {
+                let number = value()?;
+                port = number
+                    .parse()
+                    .ok()
+                    .filter(|port| *port > 0)
+                    .ok_or_else(|| {
+                        invalid(&option, &format!("expected a port number, got {}", number))
+                    })?;
+            }
+            _ if arg.starts_with('-') => return Err(invalid("option", &arg)),
+            _ if rom.is_some() => return Err(invalid("argument", &arg)),
+            _ => rom = Some(arg),
+        }
+    }
+
+    let rom = rom.ok_or_else(|| invalid("ROM", "no ROM path given"))?;
+    let mut config = match platform {
+        Some(platform) => crate::config::EmulatorConfig::for_platform(&platform),
+        None => crate::config::EmulatorConfig {
+            quirks: crate::quirks::Quirks::SCHIP_MODERN,
+            ..crate::config::EmulatorConfig::for_platform(&crate::platform::Platform::SCHIP)
+        },
+    };
+    if let Some(quirks) = quirks {
+        config.quirks = quirks;
+    }
+    for (name, quirk_value) in quirk_overrides {
+        apply_quirk(&mut config.quirks, &name, &quirk_value)?;
+    }
+    if let Some(instructions_per_frame) = instructions_per_frame {
+        config.instructions_per_frame = instructions_per_frame;
+    }
+    config.rng_seed = rng_seed;
+
+    let options = RunOptions {
+        rom,
+        config,
+        frontend,
+        mute,
+        flags_dir,
+    };
+    Ok(match mode.as_deref() {
+        Some("debug") => Command::Debug(options),
+        Some("gdb") => Command::Gdb(GdbOptions { run: options, port }),
+        _ => Command::Run(options),
+    })
+}
+
+fn parse_disasm(args: impl Iterator<Item = String>) -> Result<Command, crate::error::Chip8Error> {
+    let mut args = args;
+    let mut rom = None;
+    let mut platform = crate::platform::Platform::SCHIP;
+    let mut syntax = crate::disassembler::Syntax::Octo;
+    while let Some(arg) = args.next() {
+        let (option, inline_value) = split_option(&arg);
+        let mut value = || option_value(&option, &inline_value, &mut args);
+        match option.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--platform" => platform = parse_platform(&option, &value()?)?,
+            "--syntax" => {
+                let name = value()?;
+                syntax = crate::disassembler::Syntax::from_name(&name).ok_or_else(|| {
+                    invalid(&option, &format!("expected octo or cowgod, got {}", name))
+                })?;
+            }
+            _ if arg.starts_with('-') => return Err(invalid("option", &arg)),
+            _ if rom.is_some() => return Err(invalid("argument", &arg)),
+            _ => rom = Some(arg),
+        }
+    }
+    Ok(Command::Disasm(DisasmOptions {
+        rom: rom.ok_or_else(|| invalid("ROM", "no ROM path given"))?,
+        platform,
+        syntax,
+    }))
+}
+
+fn parse_asm(args: impl Iterator<Item = String>) -> Result<Command, crate::error::Chip8Error> {
+    let mut args = args;
+    let mut source = None;
+    let mut output = None;
+    let mut symbols = None;
+    let mut platform = crate::platform::Platform::SCHIP;
+    while let Some(arg) = args.next() {
+        let (option, inline_value) = split_option(&arg);
+        let mut value = || option_value(&option, &inline_value, &mut args);
+        match option.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--platform" => platform = parse_platform(&option, &value()?)?,
+            "-o" | "--output" => output = Some(value()?),
+            "--symbols" => symbols = Some(value()?),
+            _ if arg.starts_with('-') => return Err(invalid("option", &arg)),
+            _ if source.is_some() => return Err(invalid("argument", &arg)),
+            _ => source = Some(arg),
+        }
+    }
+    let source: String = source.ok_or_else(|| invalid("source", "no source path given"))?;
+    let output = output.unwrap_or_else(|| {
+        std::path::Path::new(&source)
+            .with_extension("ch8")
+            .to_string_lossy()
+            .into_owned()
+    });
+    Ok(Command::Asm(AsmOptions {
+        source,
+        output,
+        symbols,
+        platform,
+    }))
+}
+
+/// Advice to print after an error that stops a ROM from loading, if there is any.
+pub fn hint(error: &crate::error::Chip8Error) -> Option<&'static str> {
+    match error {
+        crate::error::Chip8Error::RomUnreadable { .. } => {
+            Some("check the path to the ROM, or run chip8 --help")
+        }
+        crate::error::Chip8Error::RomTooLarge { .. } => {
+            Some("is this ROM for a platform with more memory? try --platform xochip")
+        }
+        _ => None,
+    }
+}
+
+fn invalid(argument: &str, reason: &str) -> crate::error::Chip8Error {
+    crate::error::Chip8Error::InvalidArgument {
+        argument: argument.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Options take their value from the next argument, or after an equals sign.
+fn split_option(arg: &str) -> (String, Option<String>) {
+    match arg.split_once('=') {
+        Some((option, value)) if option.starts_with("--") => {
+            (option.to_string(), Some(value.to_string()))
+        }
+        _ => (arg.to_string(), None),
+    }
+}
+
+fn option_value(
+    option: &str,
+    inline_value: &Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, crate::error::Chip8Error> {
+    inline_value
+        .clone()
+        .or_else(|| args.next())
+        .ok_or_else(|| invalid(option, "expected a value"))
+}
+
+fn parse_platform(
+    option: &str,
+    name: &str,
+) -> Result<crate::platform::Platform, crate::error::Chip8Error> {
+    crate::platform::Platform::from_name(name)
+        .ok_or_else(|| invalid(option, &format!("unknown platform {}", name)))
+}
+
+fn parse_positive(option: &str, value: &str) -> Result<usize, crate::error::Chip8Error> {
+    match value.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(invalid(
+            option,
+            &format!("expected a positive whole number, got {}", value),
+        )),
+    }
+}
+
+/// Reads 1 to 4 hex colors. Colors that are left out keep their defaults.
+fn parse_palette(
+    option: &str,
+    value: &str,
+) -> Result<crate::palette::Palette, crate::error::Chip8Error> {
+    let mut palette = crate::palette::Palette::default();
+    let colors: Vec<&str> = value.split(',').collect();
+    if colors.len() > palette.colors.len() {
+        return Err(invalid(option, "expected at most 4 colors"));
+    }
+    for (slot, color) in palette.colors.iter_mut().zip(colors) {
+        let hex = color.trim().trim_start_matches('#');
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)
+            .ok_or_else(|| {
+                invalid(
+                    option,
+                    &format!("expected a color like ff8000, got {}", color),
+                )
+            })?;
+        let [_, red, green, blue] = rgb.to_be_bytes();
+        *slot = [red, green, blue];
+    }
+    Ok(palette)
+}
+
+fn apply_quirk(
+    quirks: &mut crate::quirks::Quirks,
+    name: &str,
+    value: &str,
+) -> Result<(), crate::error::Chip8Error> {
+    let argument = format!("quirk {}", name);
+    if name == "memory-increment" {
+        quirks.memory_increment = match value {
+            "none" => crate::quirks::MemoryIncrement::None,
+            "x" => crate::quirks::MemoryIncrement::X,
+            "x+1" => crate::quirks::MemoryIncrement::XPlusOne,
+            _ => return Err(invalid(&argument, "expected none, x or x+1")),
+        };
+        return Ok(());
+    }
+    let switch = match name {
+        "vf-reset" => &mut quirks.vf_reset,
+        "shift-uses-vy" => &mut quirks.shift_uses_vy,
+        "jump-with-vx" => &mut quirks.jump_with_vx,
+        "clip-sprites" => &mut quirks.clip_sprites,
+        "display-wait" => &mut quirks.display_wait,
+        "key-on-release" => &mut quirks.key_on_release,
+        "count-collided-rows" => &mut quirks.count_collided_rows,
+        _ => return Err(invalid("quirk", name)),
+    };
+    *switch = match value {
+        "on" | "true" | "1" => true,
+        "off" | "false" | "0" => false,
+        _ => return Err(invalid(&argument, "expected on or off")),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::{hint, parse, AsmOptions, Command, DisasmOptions, GdbOptions, RunOptions};
+    use crate::config::EmulatorConfig;
+    use crate::disassembler::Syntax;
+    use crate::error::Chip8Error;
+    use crate::platform::Platform;
+    use crate::quirks::{MemoryIncrement, Quirks};
+
+    fn run_options(args: &[&str]) -> Result<RunOptions, Chip8Error> {
+        match parse(args.iter().map(|arg| arg.to_string()))? {
+            Command::Run(options) => Ok(options),
+            other => panic!("expected run options, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let options = run_options(&["game.ch8"]).unwrap();
+        assert_eq!(options.rom, "game.ch8");
+        assert_eq!(options.config.quirks, Quirks::SCHIP_MODERN);
+        assert_eq!(
+            options.config.instruction_set,
+            Platform::SCHIP.instruction_set
+        );
+        assert_eq!(options.config.rng_seed, None);
+        assert_eq!(options.frontend, Default::default());
+        assert!(!options.mute);
+        assert_eq!(options.flags_dir, None);
+        assert_eq!(
+            parse(["game.ch8".to_string(), "--help".to_string()]),
+            Ok(Command::Help)
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options = run_options(&[
+            "--platform",
+            "xochip",
+            "--quirk",
+            "clip-sprites=on",
+            "--quirk=memory-increment=x",
+            "--ipf=50",
+            "--scale",
+            "4",
+            "--palette",
+            "#101010,FF8000",
+            "--mute",
+            "--seed",
+            "42",
+            "--paused",
+            "game.ch8",
+        ])
+        .unwrap();
+        let mut config = EmulatorConfig::for_platform(&Platform::XO_CHIP);
+        config.quirks.clip_sprites = true;
+        config.quirks.memory_increment = MemoryIncrement::X;
+        config.instructions_per_frame = 50;
+        config.rng_seed = Some(42);
+        assert_eq!(options.config, config);
+        assert_eq!(options.frontend.scale, 4);
+        assert_eq!(options.frontend.palette.colors[0], [0x10, 0x10, 0x10]);
+        assert_eq!(options.frontend.palette.colors[1], [0xFF, 0x80, 0x00]);
+        assert_eq!(options.frontend.palette.colors[2], [0xAA, 0xAA, 0xAA]);
+        assert!(options.mute && options.frontend.paused);
+
+        // a quirk preset replaces the platform's quirks
+        let options = run_options(&["--platform", "vip", "--quirks", "chip48", "a.ch8"]).unwrap();
+        assert_eq!(options.config.quirks, Quirks::CHIP_48);
+        assert_eq!(options.config.memory, Platform::COSMAC_VIP.memory);
+    }
+
+    #[test]
+    fn test_flags_dir() {
+        let options = run_options(&["--flags-dir", "saves/flags", "game.ch8"]).unwrap();
+        assert_eq!(options.flags_dir.as_deref(), Some("saves/flags"));
+        let options = run_options(&["--flags-dir=/tmp/flags", "game.ch8"]).unwrap();
+        assert_eq!(options.flags_dir.as_deref(), Some("/tmp/flags"));
+        assert!(run_options(&["game.ch8", "--flags-dir"]).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let reason = |args: &[&str]| match run_options(args) {
+            Err(Chip8Error::InvalidArgument { argument, reason }) => (argument, reason),
+            other => panic!("expected an invalid argument, got {:?}", other),
+        };
+        assert_eq!(
+            reason(&[]),
+            ("ROM".to_string(), "no ROM path given".to_string())
+        );
+        assert_eq!(
+            reason(&["--platform", "nes", "a.ch8"]).1,
+            "unknown platform nes"
+        );
+        assert_eq!(reason(&["--ipf", "0", "a.ch8"]).0, "--ipf");
+        assert_eq!(reason(&["a.ch8", "--scale"]).1, "expected a value");
+        assert_eq!(reason(&["--quirk", "wrap=on", "a.ch8"]).0, "quirk");
+        assert_eq!(
+            reason(&["--quirk", "vf-reset=maybe", "a.ch8"]).0,
+            "quirk vf-reset"
+        );
+        assert_eq!(reason(&["--palette", "12345", "a.ch8"]).0, "--palette");
+        assert_eq!(
+            reason(&["--fast", "a.ch8"]),
+            ("option".to_string(), "--fast".to_string())
+        );
+        assert_eq!(reason(&["a.ch8", "b.ch8"]).1, "b.ch8");
+    }
+
+    #[test]
+    fn test_disasm() {
+        let parse_args = |args: &[&str]| parse(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse_args(&["disasm", "game.ch8"]),
+            Ok(Command::Disasm(DisasmOptions {
+                rom: "game.ch8".to_string(),
+                platform: Platform::SCHIP,
+                syntax: Syntax::Octo,
+            }))
+        );
+        assert_eq!(
+            parse_args(&[
+                "disasm",
+                "--syntax=cowgod",
+                "--platform",
+                "xochip",
+                "game.ch8"
+            ]),
+            Ok(Command::Disasm(DisasmOptions {
+                rom: "game.ch8".to_string(),
+                platform: Platform::XO_CHIP,
+                syntax: Syntax::Cowgod,
+            }))
+        );
+        assert!(parse_args(&["disasm", "--syntax", "intel", "game.ch8"]).is_err());
+        // run options are not disassembler options
+        assert!(parse_args(&["disasm", "--mute", "game.ch8"]).is_err());
+        // a ROM named disasm can still be run from a path
+        assert!(matches!(
+            parse_args(&["./disasm"]),
+            Ok(Command::Run(RunOptions { rom, .. })) if rom == "./disasm"
+        ));
+    }
+
+    #[test]
+    fn test_asm() {
+        let parse_args = |args: &[&str]| parse(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse_args(&["asm", "games/pong.8o"]),
+            Ok(Command::Asm(AsmOptions {
+                source: "games/pong.8o".to_string(),
+                output: "games/pong.ch8".to_string(),
+                symbols: None,
+                platform: Platform::SCHIP,
+            }))
+        );
+        assert_eq!(
+            parse_args(&[
+                "asm",
+                "-o",
+                "out.bin",
+                "--symbols=pong.sym",
+                "--platform",
+                "eti660",
+                "pong.8o"
+            ]),
+            Ok(Command::Asm(AsmOptions {
+                source: "pong.8o".to_string(),
+                output: "out.bin".to_string(),
+                symbols: Some("pong.sym".to_string()),
+                platform: Platform::ETI_660,
+            }))
+        );
+        assert!(parse_args(&["asm"]).is_err());
+    }
+
+    #[test]
+    fn test_debug() {
+        let parse_args = |args: &[&str]| parse(args.iter().map(|arg| arg.to_string()));
+        let Ok(Command::Debug(options)) = parse_args(&["debug", "--platform", "vip", "game.ch8"])
+        else {
+            panic!("expected debug options");
+        };
+        assert_eq!(
+            options,
+            run_options(&["--platform", "vip", "game.ch8"]).unwrap()
+        );
+        assert!(parse_args(&["debug", "--syntax", "octo", "game.ch8"]).is_err());
+        assert!(parse_args(&["debug", "--port", "9000", "game.ch8"]).is_err());
+    }
+
+    #[test]
+    fn test_gdb() {
+        let parse_args = |args: &[&str]| parse(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            parse_args(&["gdb", "--port=9000", "--ipf", "15", "game.ch8"]),
+            Ok(Command::Gdb(GdbOptions {
+                run: run_options(&["--ipf", "15", "game.ch8"]).unwrap(),
+                port: 9000,
+            }))
+        );
+        assert!(matches!(
+            parse_args(&["gdb", "game.ch8"]),
+            Ok(Command::Gdb(GdbOptions { port: 1234, .. }))
+        ));
+        assert!(parse_args(&["gdb", "--port", "0", "game.ch8"]).is_err());
+        assert!(parse_args(&["gdb", "--port", "65536", "game.ch8"]).is_err());
+    }
+
+    #[test]
+    fn test_hint() {
+        let too_large = Chip8Error::RomTooLarge {
+            size: 5000,
+            max_size: 3584,
+        };
+        assert!(hint(&too_large).unwrap().contains("--platform xochip"));
+        assert_eq!(hint(&Chip8Error::InvalidKey { key: 16 }), None);
+    }
+}
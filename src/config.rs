@@ -0,0 +1,47 @@
+/// 12 instructions a frame -> 720 instructions per second at 60 FPS.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 12;
+
+/// Runtime settings for the emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmulatorConfig {
+    /// Which extensions to the original instructions are understood.
+    pub instruction_set: crate::instruction::InstructionSet,
+    /// How instructions that differ between interpreters behave.
+    pub quirks: crate::quirks::Quirks,
+    /// How many instructions run in each 60 Hz frame. The timers tick once a frame regardless.
+    pub instructions_per_frame: usize,
+    /// Seeds the random number generator behind CXNN. Runs with the same seed and input are identical.
+    /// A random seed is picked if this is None.
+    pub rng_seed: Option<u64>,
+    /// Which random number generator CXNN uses.
+    pub random_mode: crate::rng::RandomMode,
+    /// How many return addresses fit on the call stack before a call overflows it.
+    pub stack_depth: usize,
+    /// Where the ROM and the fonts go, and how much memory there is.
+    pub memory: crate::platform::MemoryLayout,
+}
+
+impl EmulatorConfig {
+    pub fn new(quirks: crate::quirks::Quirks) -> Self {
+        Self {
+            instruction_set: crate::instruction::InstructionSet::Chip8,
+            quirks,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            rng_seed: None,
+            random_mode: crate::rng::RandomMode::Xorshift,
+            stack_depth: crate::stack::SCHIP_DEPTH,
+            memory: crate::platform::MemoryLayout::default(),
+        }
+    }
+
+    /// Configures the emulator as the platform. Individual settings can still be changed afterwards.
+    pub fn for_platform(platform: &crate::platform::Platform) -> Self {
+        Self {
+            instruction_set: platform.instruction_set,
+            instructions_per_frame: platform.instructions_per_frame,
+            stack_depth: platform.stack_depth,
+            memory: platform.memory,
+            ..Self::new(platform.quirks)
+        }
+    }
+}
@@ -0,0 +1,507 @@
+/// What a condition compares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// The value of VX.
+    Register(u8),
+    /// The index register.
+    Index,
+    /// The byte at an address.
+    Memory(usize),
+}
+
+/// How a condition compares its operand with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+}
+
+/// A test on the machine state that decides whether a conditional breakpoint stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub operand: Operand,
+    pub comparison: Comparison,
+    pub value: u32,
+}
+
+impl Condition {
+    /// Whether the condition holds for the emulator. Addresses outside memory never hold.
+    pub fn holds(&self, emulator: &crate::emulator::Emulator) -> bool {
+        let actual = match self.operand {
+            Operand::Register(register) => emulator.registers()[register as usize & 0xF] as u32,
+            Operand::Index => emulator.index_register(),
+            Operand::Memory(address) => match emulator.memory().get(address) {
+                Some(byte) => *byte as u32,
+                None => return false,
+            },
+        };
+        match self.comparison {
+            Comparison::Equal => actual == self.value,
+            Comparison::NotEqual => actual != self.value,
+            Comparison::Less => actual < self.value,
+            Comparison::Greater => actual > self.value,
+        }
+    }
+}
+
+/// Which memory accesses a watchpoint stops on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// A range of memory watched for accesses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub addresses: std::ops::Range<usize>,
+    pub access: Access,
+}
+
+/// A class of opcodes written the way they are documented, such as DXYN or FX55.
+/// X, Y and N match any nibble; hex digits must match exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodePattern {
+    mask: u16,
+    value: u16,
+}
+
+impl OpcodePattern {
+    /// Reads a four-character pattern, or returns None if it is not one.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        if pattern.chars().count() != 4 {
+            return None;
+        }
+        let mut mask = 0;
+        let mut value = 0;
+        for character in pattern.chars() {
+            mask <<= 4;
+            value <<= 4;
+            match character {
+                'X' | 'Y' | 'N' | 'x' | 'y' | 'n' => {}
+                digit => {
+                    value |= digit.to_digit(16)? as u16;
+                    mask |= 0xF;
+                }
+            }
+        }
+        Some(Self { mask, value })
+    }
+
+    pub fn matches(&self, opcode: u16) -> bool {
+        opcode & self.mask == self.value
+    }
+}
+
+/// Why running stopped. The instruction that caused a breakpoint, watchpoint or opcode stop has not run yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// The program counter reached a breakpoint whose condition, if any, held.
+    Breakpoint { address: usize },
+    /// The next instruction accesses the watched address.
+    Watchpoint { address: usize, access: Access },
+    /// The next instruction matches a pattern given to break_on_opcode.
+    Opcode { address: usize, opcode: u16 },
+    /// The predicate given to run_until returned true.
+    Predicate,
+    /// A step or step over finished.
+    Step,
+    /// The cycle limit was reached first.
+    CycleLimit,
+    /// The ROM is waiting for a key, which only the caller can provide.
+    WaitingForKey,
+    /// The ROM executed exit.
+    Exited,
+    /// An instruction failed. The program counter points at it.
+    Error(crate::error::Chip8Error),
+}
+
+/// Wraps an emulator to step it one instruction at a time and stop it at breakpoints, watchpoints and opcodes.
+/// The timers count down once every `instructions_per_frame` instructions, as they would at full speed.
+pub struct Debugger {
+    emulator: crate::emulator::Emulator,
+    breakpoints: std::collections::BTreeMap<usize, Option<Condition>>,
+    watchpoints: Vec<Watchpoint>,
+    opcode_patterns: Vec<OpcodePattern>,
+    /// Instructions executed since the debugger was created.
+    cycles: u64,
+    /// Instructions executed since the timers last ticked.
+    frame_cycles: u64,
+    /// The address of the instruction that stopped the last run, which the next run starts by executing.
+    resume_address: Option<usize>,
+}
+
+impl Debugger {
+    pub fn new(emulator: crate::emulator::Emulator) -> Self {
+        Self {
+            emulator,
+            breakpoints: std::collections::BTreeMap::new(),
+            watchpoints: Vec::new(),
+            opcode_patterns: Vec::new(),
+            cycles: 0,
+            frame_cycles: 0,
+            resume_address: None,
+        }
+    }
+
+    pub fn emulator(&self) -> &crate::emulator::Emulator {
+        &self.emulator
+    }
+
+    /// The emulator, for changes such as key presses between runs.
+    pub fn emulator_mut(&mut self) -> &mut crate::emulator::Emulator {
+        &mut self.emulator
+    }
+
+    pub fn into_emulator(self) -> crate::emulator::Emulator {
+        self.emulator
+    }
+
+    /// How many instructions have been executed.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Stops before the instruction at the address runs.
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address, None);
+    }
+
+    /// Stops before the instruction at the address runs, if the condition holds then.
+    pub fn add_conditional_breakpoint(&mut self, address: usize, condition: Condition) {
+        self.breakpoints.insert(address, Some(condition));
+    }
+
+    /// Returns whether there was a breakpoint at the address.
+    pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.remove(&address).is_some()
+    }
+
+    /// The breakpoints in address order, with their conditions.
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, Option<Condition>)> + '_ {
+        self.breakpoints
+            .iter()
+            .map(|(address, condition)| (*address, *condition))
+    }
+
+    /// Stops before an instruction that accesses any of the addresses in the given way.
+    pub fn add_watchpoint(&mut self, addresses: std::ops::Range<usize>, access: Access) {
+        self.watchpoints.push(Watchpoint { addresses, access });
+    }
+
+    /// Returns whether there was a watchpoint on exactly these addresses.
+    pub fn remove_watchpoint(&mut self, addresses: &std::ops::Range<usize>) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints
+            .retain(|watchpoint| watchpoint.addresses != *addresses);
+        self.watchpoints.len() != count
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Stops before every instruction whose opcode matches the pattern.
+    pub fn break_on_opcode(&mut self, pattern: OpcodePattern) {
+        self.opcode_patterns.push(pattern);
+    }
+
+    /// Stops breaking on the pattern. Returns whether it was being broken on.
+    pub fn remove_opcode_break(&mut self, pattern: OpcodePattern) -> bool {
+        let count = self.opcode_patterns.len();
+        self.opcode_patterns.retain(|other| *other != pattern);
+        self.opcode_patterns.len() != count
+    }
+
+    /// Executes one instruction, ignoring breakpoints and watchpoints.
+    pub fn step(&mut self) -> Result<crate::emulator::StepOutcome, crate::error::Chip8Error> {
+        self.resume_address = None;
+        self.execute()
+    }
+
+    /// Executes one instruction, or a whole subroutine if it is a call, stopping early like `run`.
+    pub fn step_over(&mut self, cycle_limit: u64) -> StopReason {
+        let Some(crate::instruction::Instruction::Call(_)) = self.emulator.current_instruction()
+        else {
+            return match self.step() {
+                Err(error) => StopReason::Error(error),
+                Ok(outcome) if outcome.exited => StopReason::Exited,
+                Ok(outcome) if outcome.waiting_for_key => StopReason::WaitingForKey,
+                Ok(_) => StopReason::Step,
+            };
+        };
+        // the call has returned when the stack is back to its depth, even if the subroutine recursed
+        let return_address = self.emulator.program_counter() + 2;
+        let depth = self.emulator.stack().len();
+        match self.run_until(
+            |emulator| {
+                emulator.program_counter() == return_address && emulator.stack().len() == depth
+            },
+            cycle_limit,
+        ) {
+            StopReason::Predicate => StopReason::Step,
+            reason => reason,
+        }
+    }
+
+    fn execute(&mut self) -> Result<crate::emulator::StepOutcome, crate::error::Chip8Error> {
+        let outcome = self.emulator.step()?;
+        self.cycles += 1;
+        self.frame_cycles += 1;
+        // the timers run at 60 Hz, once for every frame's worth of instructions
+        if self.frame_cycles >= self.emulator.config().instructions_per_frame.max(1) as u64 {
+            self.frame_cycles = 0;
+            self.emulator.tick_timers();
+        }
+        Ok(outcome)
+    }
+
+    /// Runs until a breakpoint, watchpoint or opcode stops it, or `cycle_limit` instructions have run.
+    pub fn run(&mut self, cycle_limit: u64) -> StopReason {
+        self.run_until(|_| false, cycle_limit)
+    }
+
+    /// Runs like `run`, and also stops once the predicate holds after an instruction.
+    /// An instruction that stopped the last run does not stop this one, so that running again after a stop moves on.
+    pub fn run_until(
+        &mut self,
+        mut predicate: impl FnMut(&crate::emulator::Emulator) -> bool,
+        cycle_limit: u64,
+    ) -> StopReason {
+        for _ in 0..cycle_limit {
+            let address = self.emulator.program_counter();
+            if self.resume_address.take() != Some(address) {
+                if let Some(reason) = self.stop_reason() {
+                    self.resume_address = Some(address);
+                    return reason;
+                }
+            }
+            match self.execute() {
+                Err(error) => return StopReason::Error(error),
+                Ok(outcome) if outcome.exited => return StopReason::Exited,
+                Ok(outcome) if outcome.waiting_for_key => return StopReason::WaitingForKey,
+                Ok(_) => {}
+            }
+            if predicate(&self.emulator) {
+                return StopReason::Predicate;
+            }
+        }
+        StopReason::CycleLimit
+    }
+
+    /// Why the instruction at the program counter should not run yet, if it should not.
+    fn stop_reason(&self) -> Option<StopReason> {
+        let address = self.emulator.program_counter();
+        match self.breakpoints.get(&address) {
+            Some(None) => return Some(StopReason::Breakpoint { address }),
+            Some(Some(condition)) if condition.holds(&self.emulator) => {
+                return Some(StopReason::Breakpoint { address })
+            }
+            _ => {}
+        }
+
+        if !self.watchpoints.is_empty() {
+            let accesses = self.emulator.memory_access();
+            for watchpoint in &self.watchpoints {
+                let touched = [
+                    (Access::Read, &accesses.reads),
+                    (Access::Write, &accesses.writes),
+                ]
+                .into_iter()
+                .filter(|(access, _)| {
+                    watchpoint.access == Access::ReadWrite || watchpoint.access == *access
+                })
+                .find_map(|(access, range)| {
+                    let first = range.start.max(watchpoint.addresses.start);
+                    (first < range.end.min(watchpoint.addresses.end)).then_some((first, access))
+                });
+                if let Some((address, access)) = touched {
+                    return Some(StopReason::Watchpoint { address, access });
+                }
+            }
+        }
+
+        let opcode = self
+            .emulator
+            .memory()
+            .get(address..address + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))?;
+        self.opcode_patterns
+            .iter()
+            .any(|pattern| pattern.matches(opcode))
+            .then_some(StopReason::Opcode { address, opcode })
+    }
+}
+
+#[cfg(test)]
+mod debugger_tests {
+    use super::{Access, Comparison, Condition, Debugger, OpcodePattern, Operand, StopReason};
+    use crate::emulator::Emulator;
+
+    const COUNTER: &str = "
+        : main
+            v0 := 0
+        : count
+            v0 += 1
+            jump count
+    ";
+
+    #[test]
+    fn test_breakpoints() {
+        let mut debugger = Debugger::new(Emulator::assembled(COUNTER));
+        debugger.add_breakpoint(0x202);
+        assert_eq!(debugger.run(100), StopReason::Breakpoint { address: 0x202 });
+        assert_eq!(debugger.emulator().registers()[0], 0);
+        // running again gets past the breakpoint and stops at it on the next lap
+        assert_eq!(debugger.run(100), StopReason::Breakpoint { address: 0x202 });
+        assert_eq!(debugger.emulator().registers()[0], 1);
+        assert_eq!(debugger.cycles(), 3);
+
+        assert!(debugger.remove_breakpoint(0x202));
+        debugger.add_conditional_breakpoint(
+            0x204,
+            Condition {
+                operand: Operand::Register(0),
+                comparison: Comparison::Equal,
+                value: 10,
+            },
+        );
+        assert_eq!(debugger.run(100), StopReason::Breakpoint { address: 0x204 });
+        assert_eq!(debugger.emulator().registers()[0], 10);
+        assert_eq!(debugger.run(5), StopReason::CycleLimit);
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let source = "
+            i := buffer
+            v0 := 7
+            v1 := 8
+            save v1
+            i := buffer # the VIP moves I past what it saved
+            load v0
+            i := hex v0
+            sprite v0 v0 5
+        : buffer
+            0 0
+        ";
+        let mut debugger = Debugger::new(Emulator::assembled(source));
+        debugger.add_watchpoint(0x211..0x212, Access::Write);
+        assert_eq!(
+            debugger.run(100),
+            StopReason::Watchpoint {
+                address: 0x211,
+                access: Access::Write
+            }
+        );
+        // the save has not happened yet
+        assert_eq!(debugger.emulator().program_counter(), 0x206);
+        assert_eq!(debugger.emulator().memory()[0x211], 0);
+
+        debugger.add_watchpoint(0x200..0x220, Access::Read);
+        assert_eq!(
+            debugger.run(100),
+            StopReason::Watchpoint {
+                address: 0x210,
+                access: Access::Read
+            }
+        );
+        assert_eq!(debugger.emulator().memory()[0x211], 8);
+
+        // font data is outside the watched range
+        assert!(debugger.remove_watchpoint(&(0x200..0x220)));
+        assert_eq!(debugger.run(3), StopReason::CycleLimit);
+    }
+
+    #[test]
+    fn test_break_on_opcode() {
+        let source = "
+            v0 := 1
+            i := hex v0
+            sprite v0 v0 5
+            v0 := 2
+        : end
+            jump end
+        ";
+        let mut debugger = Debugger::new(Emulator::assembled(source));
+        let pattern = OpcodePattern::parse("DXYN").unwrap();
+        debugger.break_on_opcode(pattern);
+        assert_eq!(
+            debugger.run(100),
+            StopReason::Opcode {
+                address: 0x204,
+                opcode: 0xD005
+            }
+        );
+        assert!(debugger.remove_opcode_break(pattern));
+        assert_eq!(
+            OpcodePattern::parse("FX55").map(|pattern| pattern.matches(0xF355)),
+            Some(true)
+        );
+        assert_eq!(OpcodePattern::parse("DXY"), None);
+        assert_eq!(OpcodePattern::parse("GXYN"), None);
+    }
+
+    #[test]
+    fn test_step_over() {
+        let source = "
+            v0 := 1
+            add-twice
+            v2 := 3
+        : end
+            jump end
+        : add-twice
+            add-once
+            add-once
+            return
+        : add-once
+            v1 += v0
+            return
+        ";
+        let mut stepping = Debugger::new(Emulator::assembled(source));
+        assert_eq!(stepping.step_over(100), StopReason::Step);
+        assert_eq!(stepping.step_over(100), StopReason::Step);
+        assert_eq!(stepping.emulator().program_counter(), 0x204);
+        assert_eq!(stepping.emulator().registers()[1], 2);
+
+        // a breakpoint inside the subroutine still stops it
+        let mut interrupted = Debugger::new(Emulator::assembled(source));
+        interrupted.step().unwrap();
+        interrupted.add_breakpoint(0x20C);
+        assert_eq!(
+            interrupted.step_over(100),
+            StopReason::Breakpoint { address: 0x20C }
+        );
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut counter = Debugger::new(Emulator::assembled(COUNTER));
+        assert_eq!(
+            counter.run_until(|emulator| emulator.registers()[0] == 3, 100),
+            StopReason::Predicate
+        );
+        assert_eq!(counter.emulator().registers()[0], 3);
+
+        let mut waiting = Debugger::new(Emulator::assembled("v0 := key"));
+        assert_eq!(waiting.run(100), StopReason::WaitingForKey);
+        let mut failing = Debugger::new(Emulator::assembled("return"));
+        assert!(matches!(
+            failing.run(100),
+            StopReason::Error(crate::error::Chip8Error::StackUnderflow { address: 0x200 })
+        ));
+        assert_eq!(failing.emulator().program_counter(), 0x200);
+    }
+
+    #[test]
+    fn test_timers_tick_per_frame() {
+        let mut debugger =
+            Debugger::new(Emulator::assembled("v0 := 10 delay := v0 : wait jump wait"));
+        let instructions_per_frame = debugger.emulator().config().instructions_per_frame as u64;
+        debugger.run(instructions_per_frame * 4);
+        assert_eq!(debugger.emulator().delay_timer().counter, 6);
+    }
+}
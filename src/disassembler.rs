@@ -0,0 +1,522 @@
+/// How many data bytes are shown on one line of a listing.
+const DATA_BYTES_PER_LINE: usize = 8;
+
+/// The mnemonics a listing is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Cowgod's technical reference: `LD V0, 0x12`, `DRW V0, V1, 5`.
+    Cowgod,
+    /// Octo: `v0 := 0x12`, `sprite v0 v1 5`. Instructions Octo does not have use lower case Cowgod-style names.
+    Octo,
+}
+
+impl Syntax {
+    /// The syntax with the given name, as used on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cowgod" => Some(Syntax::Cowgod),
+            "octo" => Some(Syntax::Octo),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// An instruction reached by following the program from its start.
+    Instruction {
+        address: usize,
+        bytes: Vec<u8>,
+        instruction: crate::instruction::Instruction,
+    },
+    /// Bytes that are never executed, such as sprites, or only pointed at by I.
+    Data { address: usize, bytes: Vec<u8> },
+}
+
+impl Line {
+    pub fn address(&self) -> usize {
+        match self {
+            Line::Instruction { address, .. } | Line::Data { address, .. } => *address,
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Line::Instruction { bytes, .. } | Line::Data { bytes, .. } => bytes,
+        }
+    }
+}
+
+/// A disassembled ROM: its lines in address order and the labels generated for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub lines: Vec<Line>,
+    /// Names for the addresses that are called, jumped to or pointed at by I.
+    pub labels: std::collections::BTreeMap<usize, String>,
+}
+
+/// Why an address gets a label. Calls name a label over jumps, and jumps over data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Reference {
+    Data,
+    Jump,
+    Call,
+}
+
+/// Disassembles a ROM loaded at `start_address`, decoding opcodes for the instruction set.
+///
+/// Code is found by following jumps, calls and skips from the start address, so bytes that are
+/// never executed come out as data rather than as nonsense instructions.
+pub fn disassemble(
+    rom: &[u8],
+    start_address: usize,
+    instruction_set: crate::instruction::InstructionSet,
+) -> Disassembly {
+    let end_address = start_address + rom.len();
+    let decode_at = |address: usize| {
+        let offset = address.checked_sub(start_address)?;
+        let opcode = u16::from_be_bytes([*rom.get(offset)?, *rom.get(offset + 1)?]);
+        let instruction = crate::instruction::decode_for(opcode, instruction_set).ok()?;
+        (offset + instruction.length() as usize <= rom.len()).then_some(instruction)
+    };
+
+    let mut is_code = vec![false; rom.len()];
+    let mut instructions = std::collections::BTreeMap::new();
+    let mut references = std::collections::BTreeMap::new();
+    let mut reference = |address: usize, kind: Reference| {
+        let entry = references.entry(address).or_insert(kind);
+        *entry = kind.max(*entry);
+    };
+    let mut pending = vec![start_address];
+    while let Some(address) = pending.pop() {
+        let Some(instruction) = decode_at(address) else {
+            continue;
+        };
+        let offset = address - start_address;
+        let length = instruction.length() as usize;
+        // stop at code that has been seen, or that overlaps an instruction that has
+        if is_code[offset..offset + length].iter().any(|&code| code) {
+            continue;
+        }
+        is_code[offset..offset + length].fill(true);
+        instructions.insert(address, instruction);
+
+        let next = address + length;
+        match instruction {
+            crate::instruction::Instruction::Jp(target) => {
+                reference(target as usize, Reference::Jump);
+                pending.push(target as usize);
+            }
+            crate::instruction::Instruction::JpV0(target) => {
+                // only the first entry of a jump table can be followed
+                reference(target as usize, Reference::Jump);
+                pending.push(target as usize);
+            }
+            crate::instruction::Instruction::Call(target) => {
+                reference(target as usize, Reference::Call);
+                pending.push(target as usize);
+                pending.push(next);
+            }
+            crate::instruction::Instruction::Ret | crate::instruction::Instruction::Exit => {}
+            instruction if is_skip(&instruction) => {
+                let skipped = decode_at(next).map_or(2, |next| next.length() as usize);
+                pending.push(next);
+                pending.push(next + skipped);
+            }
+            instruction => {
+                if let Some(target) = index_target(&instruction, &rom[offset..]) {
+                    reference(target, Reference::Data);
+                }
+                pending.push(next);
+            }
+        }
+    }
+
+    // a label in the middle of an instruction, or outside the ROM, is left as a number
+    let labels: std::collections::BTreeMap<usize, String> = references
+        .into_iter()
+        .filter(|&(address, _)| {
+            (start_address..end_address).contains(&address)
+                && (instructions.contains_key(&address) || !is_code[address - start_address])
+        })
+        .map(|(address, kind)| {
+            let prefix = match kind {
+                Reference::Call => "sub",
+                Reference::Jump => "label",
+                Reference::Data => "data",
+            };
+            (address, format!("{}_{:03X}", prefix, address))
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    let mut address = start_address;
+    while address < end_address {
+        let offset = address - start_address;
+        if let Some(&instruction) = instructions.get(&address) {
+            let length = instruction.length() as usize;
+            lines.push(Line::Instruction {
+                address,
+                bytes: rom[offset..offset + length].to_vec(),
+                instruction,
+            });
+            address += length;
+            continue;
+        }
+        // data runs until the next instruction or label
+        let mut length = 1;
+        while length < DATA_BYTES_PER_LINE
+            && offset + length < rom.len()
+            && !is_code[offset + length]
+            && !labels.contains_key(&(address + length))
+        {
+            length += 1;
+        }
+        lines.push(Line::Data {
+            address,
+            bytes: rom[offset..offset + length].to_vec(),
+        });
+        address += length;
+    }
+
+    Disassembly { lines, labels }
+}
+
+impl Disassembly {
+    /// The listing as text: labels on their own lines, then each line's address, bytes and mnemonic.
+    pub fn listing(&self, syntax: Syntax) -> String {
+        let mut listing = String::new();
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.address()) {
+                match syntax {
+                    Syntax::Cowgod => listing.push_str(&format!("{}:\n", label)),
+                    Syntax::Octo => listing.push_str(&format!(": {}\n", label)),
+                }
+            }
+            let bytes: Vec<String> = line
+                .bytes()
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            let text = match line {
+                Line::Instruction {
+                    instruction, bytes, ..
+                } => self.mnemonic(instruction, bytes, syntax),
+                Line::Data { bytes, .. } => {
+                    let bytes: Vec<String> =
+                        bytes.iter().map(|byte| format!("{:#04X}", byte)).collect();
+                    match syntax {
+                        Syntax::Cowgod => format!("DB {}", bytes.join(", ")),
+                        Syntax::Octo => bytes.join(" "),
+                    }
+                }
+            };
+            listing.push_str(&format!(
+                "{:03X}  {:<24}{}\n",
+                line.address(),
+                bytes.join(" "),
+                text
+            ));
+        }
+        listing
+    }
+
+    /// The instruction in the given syntax, with addresses replaced by this disassembly's labels.
+    /// `bytes` are the instruction's bytes, which hold the addresses of the 4-byte instructions.
+    pub fn mnemonic(
+        &self,
+        instruction: &crate::instruction::Instruction,
+        bytes: &[u8],
+        syntax: Syntax,
+    ) -> String {
+        mnemonic(instruction, bytes, syntax, Some(&self.labels))
+    }
+}
+
+/// The instruction in the given syntax, with addresses replaced by their labels if there are any.
+/// `bytes` are the instruction's bytes, which hold the addresses of the 4-byte instructions.
+pub fn mnemonic(
+    instruction: &crate::instruction::Instruction,
+    bytes: &[u8],
+    syntax: Syntax,
+    labels: Option<&std::collections::BTreeMap<usize, String>>,
+) -> String {
+    use crate::instruction::Instruction;
+    let name = |address: usize| labels.and_then(|labels| labels.get(&address)).cloned();
+    let label = |address: usize| name(address).unwrap_or_else(|| format!("{:#05X}", address));
+    let long_target = || index_target(instruction, bytes).map_or_else(String::new, label);
+    match syntax {
+        Syntax::Cowgod => match *instruction {
+            Instruction::Sys(address) => format!("SYS {:#05X}", address),
+            Instruction::MegaOff => "MEGAOFF".to_string(),
+            Instruction::MegaOn => "MEGAON".to_string(),
+            Instruction::Ldhi(_) => format!("LDHI {}", long_target()),
+            Instruction::Ldpal(n) => format!("LDPAL {}", n),
+            Instruction::Sprw(n) => format!("SPRW {}", n),
+            Instruction::Sprh(n) => format!("SPRH {}", n),
+            Instruction::Alpha(n) => format!("ALPHA {:#04X}", n),
+            Instruction::Digisnd(n) => format!("DIGISND {}", n),
+            Instruction::Stopsnd => "STOPSND".to_string(),
+            Instruction::Bmode(n) => format!("BMODE {}", n),
+            Instruction::Ccol(n) => format!("CCOL {:#04X}", n),
+            Instruction::Scru(n) => format!("SCRU {}", n),
+            Instruction::ClsHires => "CLSH".to_string(),
+            Instruction::Bgcol => "BGCOL".to_string(),
+            Instruction::Col { x, y, n } => format!("COL V{:X}, V{:X}, {}", x, y, n),
+            Instruction::Scd(n) => format!("SCD {}", n),
+            Instruction::Scu(n) => format!("SCU {}", n),
+            Instruction::Cls => "CLS".to_string(),
+            Instruction::Ret => "RET".to_string(),
+            Instruction::Scr => "SCR".to_string(),
+            Instruction::Scl => "SCL".to_string(),
+            Instruction::Exit => "EXIT".to_string(),
+            Instruction::Low => "LOW".to_string(),
+            Instruction::High => "HIGH".to_string(),
+            Instruction::Jp(address) => format!("JP {}", label(address as usize)),
+            Instruction::Call(address) => format!("CALL {}", label(address as usize)),
+            Instruction::SeByte { x, byte } => format!("SE V{:X}, {:#04X}", x, byte),
+            Instruction::SneByte { x, byte } => format!("SNE V{:X}, {:#04X}", x, byte),
+            Instruction::SeReg { x, y } => format!("SE V{:X}, V{:X}", x, y),
+            Instruction::SaveRange { x, y } => format!("SAVE V{:X}, V{:X}", x, y),
+            Instruction::LoadRange { x, y } => format!("LOAD V{:X}, V{:X}", x, y),
+            Instruction::LdByte { x, byte } => format!("LD V{:X}, {:#04X}", x, byte),
+            Instruction::AddByte { x, byte } => format!("ADD V{:X}, {:#04X}", x, byte),
+            Instruction::LdReg { x, y } => format!("LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => format!("OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => format!("AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => format!("XOR V{:X}, V{:X}", x, y),
+            Instruction::AddReg { x, y } => format!("ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub { x, y } => format!("SUB V{:X}, V{:X}", x, y),
+            Instruction::Shr { x, y } => format!("SHR V{:X}, V{:X}", x, y),
+            Instruction::Subn { x, y } => format!("SUBN V{:X}, V{:X}", x, y),
+            Instruction::Shl { x, y } => format!("SHL V{:X}, V{:X}", x, y),
+            Instruction::SneReg { x, y } => format!("SNE V{:X}, V{:X}", x, y),
+            Instruction::LdI(address) => format!("LD I, {}", label(address as usize)),
+            Instruction::JpV0(address) => format!("JP V0, {}", label(address as usize)),
+            Instruction::Rnd { x, byte } => format!("RND V{:X}, {:#04X}", x, byte),
+            Instruction::Drw { x, y, n } => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::Skp(x) => format!("SKP V{:X}", x),
+            Instruction::Sknp(x) => format!("SKNP V{:X}", x),
+            Instruction::SkpSecond(x) => format!("SKP2 V{:X}", x),
+            Instruction::SknpSecond(x) => format!("SKNP2 V{:X}", x),
+            Instruction::Plane(n) => format!("PLANE {}", n),
+            Instruction::Audio => "AUDIO".to_string(),
+            Instruction::LdILong => format!("LD I, LONG {}", long_target()),
+            Instruction::LdVxDt(x) => format!("LD V{:X}, DT", x),
+            Instruction::LdVxK(x) => format!("LD V{:X}, K", x),
+            Instruction::LdDtVx(x) => format!("LD DT, V{:X}", x),
+            Instruction::LdStVx(x) => format!("LD ST, V{:X}", x),
+            Instruction::AddI(x) => format!("ADD I, V{:X}", x),
+            Instruction::LdF(x) => format!("LD F, V{:X}", x),
+            Instruction::LdHf(x) => format!("LD HF, V{:X}", x),
+            Instruction::LdRVx(x) => format!("LD R, V{:X}", x),
+            Instruction::LdVxR(x) => format!("LD V{:X}, R", x),
+            Instruction::Pitch(x) => format!("PITCH V{:X}", x),
+            Instruction::LdBcd(x) => format!("LD B, V{:X}", x),
+            Instruction::LdIVx(x) => format!("LD [I], V{:X}", x),
+            Instruction::LdVxI(x) => format!("LD V{:X}, [I]", x),
+        },
+        Syntax::Octo => match *instruction {
+            // Octo has no machine code calls, so they are written as the raw bytes
+            Instruction::Sys(address) => {
+                format!("{:#04X} {:#04X}", address >> 8, address & 0xFF)
+            }
+            Instruction::MegaOff => "megaoff".to_string(),
+            Instruction::MegaOn => "megaon".to_string(),
+            Instruction::Ldhi(_) => format!("ldhi {}", long_target()),
+            Instruction::Ldpal(n) => format!("ldpal {}", n),
+            Instruction::Sprw(n) => format!("sprw {}", n),
+            Instruction::Sprh(n) => format!("sprh {}", n),
+            Instruction::Alpha(n) => format!("alpha {:#04X}", n),
+            Instruction::Digisnd(n) => format!("digisnd {}", n),
+            Instruction::Stopsnd => "stopsnd".to_string(),
+            Instruction::Bmode(n) => format!("bmode {}", n),
+            Instruction::Ccol(n) => format!("ccol {:#04X}", n),
+            Instruction::Scru(n) => format!("scru {}", n),
+            Instruction::ClsHires => "clsh".to_string(),
+            Instruction::Bgcol => "bgcol".to_string(),
+            Instruction::Col { x, y, n } => format!("col v{:x} v{:x} {}", x, y, n),
+            Instruction::Scd(n) => format!("scroll-down {}", n),
+            Instruction::Scu(n) => format!("scroll-up {}", n),
+            Instruction::Cls => "clear".to_string(),
+            Instruction::Ret => "return".to_string(),
+            Instruction::Scr => "scroll-right".to_string(),
+            Instruction::Scl => "scroll-left".to_string(),
+            Instruction::Exit => "exit".to_string(),
+            Instruction::Low => "lores".to_string(),
+            Instruction::High => "hires".to_string(),
+            Instruction::Jp(address) => format!("jump {}", label(address as usize)),
+            // a label on its own calls it
+            Instruction::Call(address) => match name(address as usize) {
+                Some(name) => name,
+                None => format!(":call {:#05X}", address),
+            },
+            // Octo's conditions say when the next instruction runs, the opposite of the skip
+            Instruction::SeByte { x, byte } => format!("if v{:x} != {:#04X} then", x, byte),
+            Instruction::SneByte { x, byte } => format!("if v{:x} == {:#04X} then", x, byte),
+            Instruction::SeReg { x, y } => format!("if v{:x} != v{:x} then", x, y),
+            Instruction::SaveRange { x, y } => format!("save v{:x} - v{:x}", x, y),
+            Instruction::LoadRange { x, y } => format!("load v{:x} - v{:x}", x, y),
+            Instruction::LdByte { x, byte } => format!("v{:x} := {:#04X}", x, byte),
+            Instruction::AddByte { x, byte } => format!("v{:x} += {:#04X}", x, byte),
+            Instruction::LdReg { x, y } => format!("v{:x} := v{:x}", x, y),
+            Instruction::Or { x, y } => format!("v{:x} |= v{:x}", x, y),
+            Instruction::And { x, y } => format!("v{:x} &= v{:x}", x, y),
+            Instruction::Xor { x, y } => format!("v{:x} ^= v{:x}", x, y),
+            Instruction::AddReg { x, y } => format!("v{:x} += v{:x}", x, y),
+            Instruction::Sub { x, y } => format!("v{:x} -= v{:x}", x, y),
+            Instruction::Shr { x, y } => format!("v{:x} >>= v{:x}", x, y),
+            Instruction::Subn { x, y } => format!("v{:x} =- v{:x}", x, y),
+            Instruction::Shl { x, y } => format!("v{:x} <<= v{:x}", x, y),
+            Instruction::SneReg { x, y } => format!("if v{:x} == v{:x} then", x, y),
+            Instruction::LdI(address) => format!("i := {}", label(address as usize)),
+            Instruction::JpV0(address) => format!("jump0 {}", label(address as usize)),
+            Instruction::Rnd { x, byte } => format!("v{:x} := random {:#04X}", x, byte),
+            Instruction::Drw { x, y, n } => format!("sprite v{:x} v{:x} {}", x, y, n),
+            Instruction::Skp(x) => format!("if v{:x} -key then", x),
+            Instruction::Sknp(x) => format!("if v{:x} key then", x),
+            Instruction::SkpSecond(x) => format!("skp2 v{:x}", x),
+            Instruction::SknpSecond(x) => format!("sknp2 v{:x}", x),
+            Instruction::Plane(n) => format!("plane {}", n),
+            Instruction::Audio => "audio".to_string(),
+            Instruction::LdILong => format!("i := long {}", long_target()),
+            Instruction::LdVxDt(x) => format!("v{:x} := delay", x),
+            Instruction::LdVxK(x) => format!("v{:x} := key", x),
+            Instruction::LdDtVx(x) => format!("delay := v{:x}", x),
+            Instruction::LdStVx(x) => format!("buzzer := v{:x}", x),
+            Instruction::AddI(x) => format!("i += v{:x}", x),
+            Instruction::LdF(x) => format!("i := hex v{:x}", x),
+            Instruction::LdHf(x) => format!("i := bighex v{:x}", x),
+            Instruction::LdRVx(x) => format!("saveflags v{:x}", x),
+            Instruction::LdVxR(x) => format!("loadflags v{:x}", x),
+            Instruction::Pitch(x) => format!("pitch := v{:x}", x),
+            Instruction::LdBcd(x) => format!("bcd v{:x}", x),
+            Instruction::LdIVx(x) => format!("save v{:x}", x),
+            Instruction::LdVxI(x) => format!("load v{:x}", x),
+        },
+    }
+}
+
+/// Whether the instruction skips the one after it.
+fn is_skip(instruction: &crate::instruction::Instruction) -> bool {
+    matches!(
+        instruction,
+        crate::instruction::Instruction::SeByte { .. }
+            | crate::instruction::Instruction::SneByte { .. }
+            | crate::instruction::Instruction::SeReg { .. }
+            | crate::instruction::Instruction::SneReg { .. }
+            | crate::instruction::Instruction::Skp(_)
+            | crate::instruction::Instruction::Sknp(_)
+            | crate::instruction::Instruction::SkpSecond(_)
+            | crate::instruction::Instruction::SknpSecond(_)
+    )
+}
+
+/// The address the instruction points I at, reading long addresses from the bytes that follow its opcode.
+fn index_target(instruction: &crate::instruction::Instruction, bytes: &[u8]) -> Option<usize> {
+    match instruction {
+        crate::instruction::Instruction::LdI(address) => Some(*address as usize),
+        crate::instruction::Instruction::LdILong => {
+            Some(u16::from_be_bytes([*bytes.get(2)?, *bytes.get(3)?]) as usize)
+        }
+        crate::instruction::Instruction::Ldhi(high) => {
+            Some(u32::from_be_bytes([0, *high, *bytes.get(2)?, *bytes.get(3)?]) as usize)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod disassembler_tests {
+    use super::{disassemble, Line, Syntax};
+    use crate::instruction::{Instruction, InstructionSet};
+
+    // 200: call 208, 202: i := 20C, 204: sprite, 206: jump 206, 208: skip, 20A: ret, 20C: sprite data
+    const ROM: [u8; 14] = [
+        0x22, 0x08, 0xA2, 0x0C, 0xD0, 0x12, 0x12, 0x06, 0x30, 0x01, 0x00, 0xEE, 0xF0, 0x90,
+    ];
+
+    #[test]
+    fn test_disassemble() {
+        let disassembly = disassemble(&ROM, 0x200, InstructionSet::Chip8);
+        assert_eq!(
+            disassembly.lines[0],
+            Line::Instruction {
+                address: 0x200,
+                bytes: vec![0x22, 0x08],
+                instruction: Instruction::Call(0x208),
+            }
+        );
+        assert_eq!(
+            disassembly.lines.last(),
+            Some(&Line::Data {
+                address: 0x20C,
+                bytes: vec![0xF0, 0x90],
+            })
+        );
+        let labels: Vec<(usize, &str)> = disassembly
+            .labels
+            .iter()
+            .map(|(address, label)| (*address, label.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                (0x206, "label_206"),
+                (0x208, "sub_208"),
+                (0x20C, "data_20C")
+            ]
+        );
+
+        assert_eq!(
+            disassembly.listing(Syntax::Octo),
+            "\
+200  22 08                   sub_208
+202  A2 0C                   i := data_20C
+204  D0 12                   sprite v0 v1 2
+: label_206
+206  12 06                   jump label_206
+: sub_208
+208  30 01                   if v0 != 0x01 then
+20A  00 EE                   return
+: data_20C
+20C  F0 90                   0xF0 0x90
+"
+        );
+        let cowgod = disassembly.listing(Syntax::Cowgod);
+        assert!(cowgod.contains("sub_208:\n208  30 01                   SE V0, 0x01\n"));
+        assert!(cowgod.contains("LD I, data_20C"));
+        assert!(cowgod.contains("DB 0xF0, 0x90"));
+    }
+
+    #[test]
+    fn test_disassemble_for_platform() {
+        // F000 2208: i := long, then 00FF, which is only an instruction in SUPER-CHIP and later
+        let rom = [0xF0, 0x00, 0x02, 0x06, 0x00, 0xFF, 0xAA];
+        let xo_chip = disassemble(&rom, 0x200, InstructionSet::XoChip);
+        assert_eq!(xo_chip.lines.len(), 3);
+        assert!(xo_chip.listing(Syntax::Octo).contains(
+            "F0 00 02 06             i := long data_206\n204  00 FF                   hires\n"
+        ));
+
+        // without XO-CHIP, F000 is not an opcode and nothing after it is reached
+        let chip8 = disassemble(&rom, 0x200, InstructionSet::Chip8);
+        assert!(chip8
+            .lines
+            .iter()
+            .all(|line| matches!(line, Line::Data { .. })));
+
+        // a skip over a 4-byte instruction continues after all of it
+        let rom = [0x30, 0x00, 0xF0, 0x00, 0x00, 0x00, 0x00, 0xE0];
+        let disassembly = disassemble(&rom, 0x200, InstructionSet::XoChip);
+        assert_eq!(
+            disassembly.lines.last(),
+            Some(&Line::Instruction {
+                address: 0x206,
+                bytes: vec![0x00, 0xE0],
+                instruction: Instruction::Cls,
+            })
+        );
+    }
+}
@@ -1,41 +1,85 @@
-use rodio::Source;
 use winit::event::ElementState::{Pressed, Released};
-use winit::event::{Event, KeyboardInput, StartCause, WindowEvent};
-
-const FPS: f64 = 60.0;
-const MEMORY_SIZE: usize = 4096;
-const FONT_MEMORY_START: usize = 0x50;
-const FONT: [u8; 80] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-    0x20, 0x60, 0x20, 0x20, 0x70, // 1
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
+
+/// XO-CHIP's pitch register starts at 64, which plays audio patterns at 4000 bits a second.
+const DEFAULT_PITCH: u8 = 64;
+
+/// What happened while executing a single instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepOutcome {
+    pub opcode: u16,
+    /// The opcode as decoded for the configured instruction set.
+    pub instruction: crate::instruction::Instruction,
+    pub screen_changed: bool,
+    pub waiting_for_key: bool,
+    /// The ROM executed 00FD and the interpreter should quit.
+    pub exited: bool,
+}
+
+/// What happened while running a single frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameOutcome {
+    pub instructions_executed: usize,
+    pub screen_changed: bool,
+    pub waiting_for_key: bool,
+    pub exited: bool,
+}
+
+/// The memory an instruction reads or writes through the index register.
+/// Ranges are empty for instructions that do neither.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub reads: std::ops::Range<usize>,
+    pub writes: std::ops::Range<usize>,
+}
 
 pub struct Emulator {
-    memory: [u8; MEMORY_SIZE],
+    /// Usually 4 KiB, 64 KiB with XO-CHIP or 16 MiB with MEGA-CHIP.
+    memory: Vec<u8>,
     stack: crate::stack::Stack,
     delay_timer: crate::timer::Timer,
     sound_timer: crate::timer::Timer,
     pressed: std::collections::HashMap<u8, winit::event::ElementState>,
     pressed_hex_map: bimap::BiHashMap<u8, u8>,
+    /// The CHIP-8X second keypad.
+    second_pressed_hex_map: bimap::BiHashMap<u8, u8>,
+    program_counter: usize,
+    index_register: u32,
+    registers: [u8; 16],
+    framebuffer: crate::framebuffer::Framebuffer,
+    /// The XO-CHIP planes that drawing, clearing and scrolling affect.
+    selected_planes: u8,
+    /// The XO-CHIP audio pattern loaded by F002, if any.
+    audio_pattern: Option<[u8; 16]>,
+    /// The XO-CHIP playback pitch set by FX3A.
+    pitch: u8,
+    megachip: crate::megachip::MegaChipState,
+    rng: crate::rng::Rng,
+    config: crate::config::EmulatorConfig,
+    audio: Box<dyn crate::audio::AudioSink>,
+    frames_run: u64,
+    /// Identifies the ROM to the flag store.
+    rom_hash: u64,
+    flags: [u8; crate::flags::MAX_FLAGS],
+    flag_store: Box<dyn crate::flags::FlagStore>,
+}
+
+/// A snapshot of everything that changes while a ROM runs, including the random number generator,
+/// so that restoring it replays exactly the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveState {
+    memory: Vec<u8>,
+    stack: crate::stack::Stack,
+    delay_timer: crate::timer::Timer,
+    sound_timer: crate::timer::Timer,
     program_counter: usize,
-    index_register: u16,
+    index_register: u32,
     registers: [u8; 16],
-    has_cosmac_vip_instructions: bool,
-    enable_sound: bool,
+    framebuffer: crate::framebuffer::Framebuffer,
+    selected_planes: u8,
+    audio_pattern: Option<[u8; 16]>,
+    pitch: u8,
+    megachip: crate::megachip::MegaChipState,
+    rng: crate::rng::Rng,
 }
 
 impl Emulator {
@@ -43,191 +87,459 @@ impl Emulator {
     ///
     /// # Arguments:
     /// * `file_path`: An optional path to the ROM.
-    /// * `has_cosmac_vip_instructions`: Determines whether some functions behave like they would on the COSMAC VIP.
+    /// * `config`: Determines the quirks, the speed and the memory layout of the emulator.
     pub async fn new(
         file_path: Option<&str>,
-        has_cosmac_vip_instructions: bool,
-        disable_sound: bool,
-    ) -> Self {
-        let mut memory = match file_path {
-            Some(path) => Self::load_memory_from_rom(path),
-            None => [0; MEMORY_SIZE],
+        config: crate::config::EmulatorConfig,
+    ) -> Result<Self, crate::error::Chip8Error> {
+        let rom = match file_path {
+            Some(path) => {
+                std::fs::read(path).map_err(|error| crate::error::Chip8Error::RomUnreadable {
+                    path: path.to_string(),
+                    reason: error.to_string(),
+                })?
+            }
+            None => Vec::new(),
         };
-        memory[FONT_MEMORY_START..FONT_MEMORY_START + FONT.len()].clone_from_slice(&FONT);
+        Self::from_rom(&rom, config)
+    }
+
+    /// Builds the emulator with a ROM that is already in memory, such as one just assembled.
+    pub fn from_rom(
+        rom: &[u8],
+        config: crate::config::EmulatorConfig,
+    ) -> Result<Self, crate::error::Chip8Error> {
+        let seed = config.rng_seed.unwrap_or_else(rand::random);
+        let layout = config.memory;
+        // XO-CHIP and MEGA-CHIP need their larger memory even with a layout made for CHIP-8
+        let mut memory = vec![0; layout.memory_size.max(config.instruction_set.memory_size())];
+        if layout.initial_memory == crate::platform::InitialMemory::Random {
+            // a generator of its own, so that CXNN gives the same numbers either way
+            let mut fill = crate::rng::Rng::new(crate::rng::RandomMode::Xorshift, !seed);
+            memory
+                .iter_mut()
+                .for_each(|byte| *byte = fill.next_byte(&[]));
+        }
+        let rom_hash = Self::load_memory_from_rom(rom, &mut memory, layout.start_address)?;
+        memory[layout.font_address..layout.font_address + layout.font.len()]
+            .clone_from_slice(layout.font);
+        if let Some(big_font) = layout.big_font {
+            let big_font_address = layout.big_font_address();
+            memory[big_font_address..big_font_address + big_font.len()].clone_from_slice(big_font);
+        }
 
-        let stack = crate::stack::Stack::new();
+        let stack = crate::stack::Stack::new(config.stack_depth);
         let delay_timer = crate::timer::Timer::new();
         let sound_timer = crate::timer::Timer::new();
 
-        let program_counter = 0x200;
+        let program_counter = layout.start_address;
         let index_register = 0;
 
         let pressed = Self::load_pressed();
         let pressed_hex_map = Self::load_pressed_hex_map();
+        let second_pressed_hex_map = Self::load_second_pressed_hex_map();
 
         let registers = [0; 16];
 
-        Self {
+        let width = crate::screen::SCREEN_WIDTH as usize;
+        let framebuffer = match config.instruction_set {
+            crate::instruction::InstructionSet::TwoPageHires => {
+                crate::framebuffer::Framebuffer::new(
+                    width,
+                    crate::screen::TWO_PAGE_SCREEN_HEIGHT as usize,
+                )
+            }
+            crate::instruction::InstructionSet::Chip8X => {
+                crate::framebuffer::Framebuffer::with_color_zones(
+                    width,
+                    crate::screen::SCREEN_HEIGHT as usize,
+                )
+            }
+            _ => crate::framebuffer::Framebuffer::new(width, crate::screen::SCREEN_HEIGHT as usize),
+        };
+
+        let rng = crate::rng::Rng::new(config.random_mode, seed);
+
+        Ok(Self {
             memory,
             stack,
             delay_timer,
             sound_timer,
             pressed,
             pressed_hex_map,
+            second_pressed_hex_map,
             program_counter,
             index_register,
             registers,
-            has_cosmac_vip_instructions,
-            enable_sound: disable_sound,
+            framebuffer,
+            selected_planes: crate::framebuffer::FIRST_PLANE,
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            megachip: crate::megachip::MegaChipState::new(),
+            rng,
+            config,
+            audio: Box::new(crate::audio::NullSink),
+            frames_run: 0,
+            rom_hash,
+            flags: [0; crate::flags::MAX_FLAGS],
+            flag_store: Box::new(crate::flags::MemoryFlagStore::new()),
+        })
+    }
+
+    /// Assembles Octo source at 0x200 into an emulator with the COSMAC VIP quirks, for tests.
+    #[cfg(test)]
+    pub(crate) fn assembled(source: &str) -> Self {
+        let program = crate::assembler::assemble(source, 0x200).unwrap();
+        Self::from_rom(
+            &program.rom,
+            crate::config::EmulatorConfig::new(crate::quirks::Quirks::COSMAC_VIP),
+        )
+        .unwrap()
+    }
+
+    /// Captures the machine state, including the random number generator.
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            memory: self.memory.clone(),
+            stack: self.stack.clone(),
+            delay_timer: self.delay_timer.clone(),
+            sound_timer: self.sound_timer.clone(),
+            program_counter: self.program_counter,
+            index_register: self.index_register,
+            registers: self.registers,
+            framebuffer: self.framebuffer.clone(),
+            selected_planes: self.selected_planes,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            megachip: self.megachip.clone(),
+            rng: self.rng,
         }
     }
 
-    /// Runs the ROM and consumes it upon completion (i.e. not passing self by reference).
-    pub async fn run(mut self) {
-        env_logger::init();
-        let event_loop = winit::event_loop::EventLoop::new();
+    /// Restores a machine state captured by save_state.
+    pub fn load_state(&mut self, state: &SaveState) {
+        self.memory = state.memory.clone();
+        self.stack = state.stack.clone();
+        self.delay_timer = state.delay_timer.clone();
+        self.sound_timer = state.sound_timer.clone();
+        self.program_counter = state.program_counter;
+        self.index_register = state.index_register;
+        self.registers = state.registers;
+        self.framebuffer = state.framebuffer.clone();
+        self.selected_planes = state.selected_planes;
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.megachip = state.megachip.clone();
+        self.rng = state.rng;
+        self.send_audio_pattern();
+    }
 
-        let window: winit::window::Window = winit::window::WindowBuilder::new()
-            .build(&event_loop)
-            .unwrap();
+    /// The display the ROM draws to.
+    pub fn framebuffer(&self) -> &crate::framebuffer::Framebuffer {
+        &self.framebuffer
+    }
+
+    /// The current runtime settings.
+    pub fn config(&self) -> &crate::config::EmulatorConfig {
+        &self.config
+    }
+
+    /// Changes how many instructions run in each frame. Takes effect from the next frame.
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: usize) {
+        self.config.instructions_per_frame = instructions_per_frame;
+    }
+
+    /// The call stack of return addresses.
+    pub fn stack(&self) -> &crate::stack::Stack {
+        &self.stack
+    }
+
+    /// Replaces where the buzzer goes, and tells the new sink about any XO-CHIP audio pattern.
+    /// Emulators start with a sink that discards it.
+    pub fn set_audio_sink(&mut self, audio: Box<dyn crate::audio::AudioSink>) {
+        self.audio = audio;
+        self.send_audio_pattern();
+    }
+
+    /// Replaces where the RPL user flags are saved, and loads the flags saved there for this ROM.
+    /// Emulators start with an empty store in memory.
+    pub fn set_flag_store(&mut self, flag_store: Box<dyn crate::flags::FlagStore>) {
+        self.flags = flag_store.load(self.rom_hash);
+        self.flag_store = flag_store;
+    }
+
+    /// The sound timer. The buzzer sounds while it is non-zero.
+    pub fn sound_timer(&self) -> &crate::timer::Timer {
+        &self.sound_timer
+    }
+
+    /// The delay timer.
+    pub fn delay_timer(&self) -> &crate::timer::Timer {
+        &self.delay_timer
+    }
+
+    /// The address of the next instruction.
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    pub fn index_register(&self) -> u32 {
+        self.index_register
+    }
+
+    /// V0 to VF.
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    /// All of memory, including the interpreter's fonts below the start address.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Moves execution to the address, for debuggers. An address outside memory fails on the next step.
+    pub fn set_program_counter(&mut self, address: usize) {
+        self.program_counter = address;
+    }
+
+    /// Sets the delay timer, for debuggers.
+    pub fn set_delay_timer(&mut self, counter: u8) {
+        self.delay_timer.counter = counter;
+    }
 
-        let mut renderer = crate::renderer::RendererState::new(window).await;
-        let timer_length = std::time::Duration::new(0, (1_000_000_000.0 / FPS) as u32);
+    /// Sets the sound timer, for debuggers.
+    pub fn set_sound_timer(&mut self, counter: u8) {
+        self.sound_timer.counter = counter;
+    }
+
+    /// Writes bytes to memory starting at the address, for debuggers.
+    pub fn poke(&mut self, address: usize, bytes: &[u8]) -> Result<(), crate::error::Chip8Error> {
+        self.check_memory_range(address, bytes.len())?;
+        self.memory[address..address + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
 
-        let (sink, source) = Self::load_sound_player();
+    /// Presses or releases a key of the first keypad by its hex value, for frontends without scancodes.
+    pub fn set_key(
+        &mut self,
+        key: u8,
+        state: winit::event::ElementState,
+    ) -> Result<(), crate::error::Chip8Error> {
+        let scancode = *self
+            .pressed_hex_map
+            .get_by_left(&key)
+            .ok_or(crate::error::Chip8Error::InvalidKey { key })?;
+        self.handle_input(&state, &(scancode as u32));
+        Ok(())
+    }
+
+    /// Counts the timers down once, as at the start of a frame, for callers that call step themselves.
+    pub fn tick_timers(&mut self) {
+        self.delay_timer.decrement();
+        self.sound_timer.decrement();
+    }
 
-        event_loop.run(move |event, _, control_flow| match event {
-            // wait a frame on init
-            Event::NewEvents(StartCause::Init)
-            | Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
-                control_flow.set_wait_until(std::time::Instant::now() + timer_length);
+    /// The memory that the instruction at the program counter will read or write through the index register.
+    pub fn memory_access(&self) -> MemoryAccess {
+        let start = self.index_register as usize;
+        let span = |length: usize| start..start + length;
+        let mut access = MemoryAccess::default();
+        match self.current_instruction() {
+            Some(crate::instruction::Instruction::Drw { n, .. }) => {
+                access.reads = span(self.sprite_length(n as usize))
             }
-            // resizing window, closing window, user input
-            Event::WindowEvent {
-                window_id,
-                ref event,
-            } if window_id == renderer.window().id() => match event {
-                WindowEvent::CloseRequested
-                | WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: Pressed,
-                            virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
-                            ..
-                        },
-                    ..
-                } => *control_flow = winit::event_loop::ControlFlow::Exit,
-                // Resize
-                WindowEvent::Resized(physical_size) => {
-                    renderer.resize(*physical_size);
-                }
-                // Scale factor changed
-                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                    renderer.resize(**new_inner_size);
-                }
-                // Pressed or released a key
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state, scancode, ..
-                        },
-                    ..
-                } => self.handle_input(state, scancode),
-                _ => {}
-            },
-            // explicit redraw request
-            Event::RedrawRequested(window_id) if window_id == renderer.window().id() => {
-                renderer.update();
-                match renderer.render() {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => renderer.resize(renderer.size),
-                    Err(wgpu::SurfaceError::OutOfMemory) => {
-                        *control_flow = winit::event_loop::ControlFlow::Exit
-                    }
-                    Err(e) => eprint!("{:?}", e),
-                }
+            Some(crate::instruction::Instruction::LdVxI(x)) => access.reads = span(x as usize + 1),
+            Some(crate::instruction::Instruction::LoadRange { x, y }) => {
+                access.reads = span(Self::register_range(x as usize, y as usize).len())
+            }
+            Some(crate::instruction::Instruction::Audio) => access.reads = span(16),
+            Some(crate::instruction::Instruction::Ldpal(count)) => {
+                access.reads = span(count as usize * 4)
             }
-            // everything else - no input or waiting
-            Event::MainEventsCleared => self.post_input_loop(&sink, &source, &mut renderer),
+            Some(crate::instruction::Instruction::Digisnd(_)) => {
+                access.reads = span(
+                    crate::megachip::Sample::HEADER_LENGTH
+                        + crate::megachip::Sample::parse(
+                            self.memory.get(start..).unwrap_or_default(),
+                            false,
+                        )
+                        .map_or(0, |sample| sample.data.len()),
+                )
+            }
+            Some(crate::instruction::Instruction::LdIVx(x)) => access.writes = span(x as usize + 1),
+            Some(crate::instruction::Instruction::SaveRange { x, y }) => {
+                access.writes = span(Self::register_range(x as usize, y as usize).len())
+            }
+            Some(crate::instruction::Instruction::LdBcd(_)) => access.writes = span(3),
             _ => {}
-        })
+        }
+        access
     }
 
-    fn post_input_loop(
-        &mut self,
-        sink: &rodio::Sink,
-        source: &rodio::source::Buffered<rodio::Decoder<std::io::BufReader<std::fs::File>>>,
-        renderer: &mut crate::renderer::RendererState,
-    ) {
-        // play sound if timer == 0
-        if (self.sound_timer.counter == 0) & self.enable_sound {
-            sink.append(source.clone());
+    /// Fetches, decodes and executes exactly one instruction.
+    /// On error, the program counter is left pointing at the instruction that failed.
+    pub fn step(&mut self) -> Result<StepOutcome, crate::error::Chip8Error> {
+        let address = self.program_counter;
+
+        // fetch
+        let opcode = self.get_opcode_from_memory()?;
+
+        // decode
+        let instruction = crate::instruction::decode_for(opcode, self.config.instruction_set)
+            .map_err(|_| crate::error::Chip8Error::UnknownOpcode { opcode, address })?;
+
+        // increment program counter for next instruction
+        self.program_counter += 2;
+
+        // execute
+        if let Err(error) = self.execute(instruction) {
+            self.program_counter = address;
+            return Err(error);
         }
 
+        Ok(StepOutcome {
+            opcode,
+            instruction,
+            screen_changed: matches!(
+                instruction,
+                crate::instruction::Instruction::Cls
+                    | crate::instruction::Instruction::Drw { .. }
+                    | crate::instruction::Instruction::Scd(_)
+                    | crate::instruction::Instruction::Scu(_)
+                    | crate::instruction::Instruction::Plane(_)
+                    | crate::instruction::Instruction::Scr
+                    | crate::instruction::Instruction::Scl
+                    | crate::instruction::Instruction::Low
+                    | crate::instruction::Instruction::High
+                    | crate::instruction::Instruction::MegaOff
+                    | crate::instruction::Instruction::MegaOn
+                    | crate::instruction::Instruction::Scru(_)
+                    | crate::instruction::Instruction::ClsHires
+                    | crate::instruction::Instruction::Bgcol
+                    | crate::instruction::Instruction::Col { .. }
+            ),
+            waiting_for_key: self.is_waiting_for_key(),
+            exited: self.has_exited(),
+        })
+    }
+
+    /// Runs one 60 Hz frame: decrements the timers and executes the instructions for this frame.
+    /// The frame ends early if the CPU starts waiting for a key, or after a draw if the display wait quirk is enabled.
+    pub fn run_frame(&mut self) -> Result<FrameOutcome, crate::error::Chip8Error> {
         // decrement timers
         self.delay_timer.decrement();
         self.sound_timer.decrement();
 
-        // 12x a frame -> 720 / instructions per second on 60 FPS
-        for _ in 0..12 {
-            // fetch
-            let instruction_bytes: [u8; 2] = self.get_instructions_from_memory();
-
-            // increment program counter for next instruction
-            self.program_counter = self.program_counter + 2;
+        let mut outcome = FrameOutcome {
+            instructions_executed: 0,
+            screen_changed: false,
+            waiting_for_key: false,
+            exited: false,
+        };
 
-            // decode and execute
-            self.parse_instruction(&instruction_bytes, renderer);
+        for _ in 0..self.config.instructions_per_frame {
+            let step = self.step()?;
+            outcome.instructions_executed += 1;
+            outcome.screen_changed |= step.screen_changed;
+            if step.waiting_for_key {
+                outcome.waiting_for_key = true;
+                break;
+            }
+            if step.exited {
+                outcome.exited = true;
+                break;
+            }
+            // with the display wait quirk, drawing waits for the vertical blank interrupt, ending the frame
+            if self.config.quirks.display_wait
+                && matches!(
+                    step.instruction,
+                    crate::instruction::Instruction::Drw { .. }
+                )
+            {
+                break;
+            }
         }
 
-        // render
-        renderer.window().request_redraw();
+        // the buzzer sounds for as long as the sound timer is non-zero
+        self.audio
+            .set_buzzer(self.frames_run, self.sound_timer.counter > 0);
+        self.frames_run += 1;
+
+        Ok(outcome)
+    }
+
+    /// Whether the current instruction is get_key, meaning the CPU is blocked until a key is pressed.
+    pub fn is_waiting_for_key(&self) -> bool {
+        matches!(
+            self.current_instruction(),
+            Some(crate::instruction::Instruction::LdVxK(_))
+        )
+    }
+
+    /// Whether the current instruction is exit, meaning the ROM has finished and the interpreter should quit.
+    pub fn has_exited(&self) -> bool {
+        matches!(
+            self.current_instruction(),
+            Some(crate::instruction::Instruction::Exit)
+        )
     }
 
     /// Processes keyboard input. Ends the get_key function if the current opcode is get_key and the desired pressed status is input.
-    fn handle_input(&mut self, state: &winit::event::ElementState, scancode: &u32) {
+    pub fn handle_input(&mut self, state: &winit::event::ElementState, scancode: &u32) {
         let casted_scancode = &(*scancode as u8);
-        if self.pressed.contains_key(casted_scancode) {
-            *self.pressed.get_mut(casted_scancode).unwrap() = *state;
+        match self.pressed.get_mut(casted_scancode) {
+            Some(press_status) => *press_status = *state,
+            // not one of the keypad keys
+            None => return,
+        }
+        // only the first keypad answers FX0A
+        if !self.pressed_hex_map.contains_right(casted_scancode) {
+            return;
         }
 
         // In get_key, we loop indefinitely until a key is pressed (or released in the COSMAC VIP)
-        let parsed_instructions =
-            crate::instruction_format::InstructionFormat::new(&self.get_instructions_from_memory());
-        let trigger_state = if self.has_cosmac_vip_instructions {
+        let trigger_state = if self.config.quirks.key_on_release {
             Released
         } else {
             Pressed
         };
 
         // Check that the current instruction is get_key
-        if (*state == trigger_state)
-            & (parsed_instructions.first_nibble == 0xF)
-            & (parsed_instructions.nibbles_3_to_4 == 0x0A)
-        {
-            self.get_key(parsed_instructions.second_nibble, Some(*casted_scancode));
+        if let Some(crate::instruction::Instruction::LdVxK(x)) = self.current_instruction() {
+            if *state == trigger_state {
+                self.get_key(x as usize, Some(*casted_scancode));
+            }
         }
     }
 
-    /// Loads sound-playing capabilities. The CHIP-8 emulator plays a beeping sound when the sound counter goes to 0.
-    fn load_sound_player() -> (
-        rodio::Sink,
-        rodio::source::Buffered<rodio::Decoder<std::io::BufReader<std::fs::File>>>,
-    ) {
-        let (_stream, handle) = rodio::OutputStream::try_default().unwrap();
-        let sink = rodio::Sink::try_new(&handle).unwrap();
-        let file = std::fs::File::open("./resources/beep.mp3").unwrap();
-        let source = rodio::Decoder::new(std::io::BufReader::new(file))
-            .unwrap()
-            .buffered();
-        (sink, source)
-    }
-
-    /// Loads the current instructions from memory.
-    fn get_instructions_from_memory(&mut self) -> [u8; 2] {
-        self.memory[self.program_counter..self.program_counter + 2]
+    /// Loads the current opcode from memory.
+    fn get_opcode_from_memory(&self) -> Result<u16, crate::error::Chip8Error> {
+        self.check_memory_range(self.program_counter, 2)?;
+        let instruction_bytes: [u8; 2] = self.memory
+            [self.program_counter..self.program_counter + 2]
             .try_into()
-            .expect("Expected to receive 2 values from memory")
+            .expect("Expected to receive 2 values from memory");
+        Ok(crate::bit_utils::append_number_bits(&instruction_bytes))
+    }
+
+    /// Decodes the instruction at the program counter, if there is a valid one.
+    pub fn current_instruction(&self) -> Option<crate::instruction::Instruction> {
+        let opcode = self.get_opcode_from_memory().ok()?;
+        crate::instruction::decode_for(opcode, self.config.instruction_set).ok()
+    }
+
+    /// Ensures that `length` bytes starting at `address` lie inside memory.
+    fn check_memory_range(
+        &self,
+        address: usize,
+        length: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        if address + length > self.memory.len() {
+            return Err(crate::error::Chip8Error::AddressOutOfRange {
+                address: address.max(self.memory.len()),
+            });
+        }
+        Ok(())
     }
 
     /// Initializes the pressed map
@@ -253,6 +565,26 @@ impl Emulator {
             (47, Released),
             (48, Released),
             (49, Released),
+            // 7890
+            (8, Released),
+            (9, Released),
+            (10, Released),
+            (11, Released),
+            // UIOP
+            (22, Released),
+            (23, Released),
+            (24, Released),
+            (25, Released),
+            // JKL;
+            (36, Released),
+            (37, Released),
+            (38, Released),
+            (39, Released),
+            // M,./
+            (50, Released),
+            (51, Released),
+            (52, Released),
+            (53, Released),
         ])
     }
 
@@ -297,73 +629,153 @@ impl Emulator {
         pressed_hex_map
     }
 
-    /// Executes an action based on the two provided actions.
-    fn parse_instruction(
+    /// Initializes the CHIP-8X second keypad's scancode hex map, which has the same layout on the keys to the right
+    ///
+    /// QWERTY Keyboard Layout:
+    /// 7 8 9 0
+    /// U I O P
+    /// J K L ;
+    /// M , . /
+    ///
+    /// Mapped to scancodes:
+    ///
+    ///  8  9 10 11
+    /// 22 23 24 25
+    /// 36 37 38 39
+    /// 50 51 52 53
+    fn load_second_pressed_hex_map() -> bimap::BiHashMap<u8, u8> {
+        let mut pressed_hex_map = bimap::BiHashMap::new();
+        pressed_hex_map.insert(0x0, 51);
+        pressed_hex_map.insert(0x1, 8);
+        pressed_hex_map.insert(0x2, 9);
+        pressed_hex_map.insert(0x3, 10);
+        pressed_hex_map.insert(0x4, 22);
+        pressed_hex_map.insert(0x5, 23);
+        pressed_hex_map.insert(0x6, 24);
+        pressed_hex_map.insert(0x7, 36);
+        pressed_hex_map.insert(0x8, 37);
+        pressed_hex_map.insert(0x9, 38);
+        pressed_hex_map.insert(0xA, 50);
+        pressed_hex_map.insert(0xB, 52);
+        pressed_hex_map.insert(0xC, 11);
+        pressed_hex_map.insert(0xD, 25);
+        pressed_hex_map.insert(0xE, 39);
+        pressed_hex_map.insert(0xF, 53);
+        pressed_hex_map
+    }
+
+    /// Executes the provided instruction.
+    fn execute(
         &mut self,
-        instruction: &[u8; 2],
-        renderer: &mut crate::renderer::RendererState,
-    ) {
-        // Parse instructions
-        let p = crate::instruction_format::InstructionFormat::new(instruction);
-
-        // Choose an instruction to execute
-        match p.first_nibble {
-            0x0 => match p.fourth_nibble {
-                0x0 => clear_screen(renderer),
-                0xE => self.stack_return(),
-                _ => {}
-            },
-            0x1 => self.jump(p.nibbles_2_to_4),
-            0x2 => self.call_subroutine(p.nibbles_2_to_4),
-            0x3 => self.skip_if_register_equals_value(p.second_nibble, p.nibbles_3_to_4),
-            0x4 => self.skip_if_register_not_equal_to_value(p.second_nibble, p.nibbles_3_to_4),
-            0x5 => self.skip_if_registers_equal(p.second_nibble, p.third_nibble),
-            0x6 => self.set_register(p.second_nibble as usize, p.nibbles_3_to_4),
-            0x7 => self.add_to_register(p.second_nibble as usize, p.nibbles_3_to_4),
-            0x8 => match p.fourth_nibble {
-                0x0 => self.set_register_to_other(p.second_nibble, p.third_nibble),
-                0x1 => self.binary_or(p.second_nibble, p.third_nibble),
-                0x2 => self.binary_and(p.second_nibble, p.third_nibble),
-                0x3 => self.binary_xor(p.second_nibble, p.third_nibble),
-                0x4 => self.add_registers(p.second_nibble, p.third_nibble),
-                0x5 => self.subtract_registers(p.second_nibble, p.third_nibble),
-                0x6 => self.right_shift_on_register(p.second_nibble, p.third_nibble),
-                0x7 => self.subtract_registers(p.third_nibble, p.second_nibble),
-                0xE => self.left_shift_on_register(p.second_nibble, p.third_nibble),
-                _ => {}
-            },
-            0x9 => self.skip_if_registers_not_equal(p.second_nibble, p.third_nibble),
-            0xA => self.set_index_register(p.nibbles_2_to_4),
-            0xB => match self.has_cosmac_vip_instructions {
-                true => self.jump_with_offset(None, p.nibbles_2_to_4),
-                false => self.jump_with_offset(Some(p.second_nibble), p.nibbles_3_to_4.into()),
-            },
-            0xC => self.random(p.second_nibble, p.nibbles_3_to_4),
-            0xD => self.draw_to_screen(
-                p.second_nibble as usize,
-                p.third_nibble as usize,
-                p.fourth_nibble as usize,
-                renderer,
-            ),
-            0xE => match p.nibbles_3_to_4 {
-                0x9E => self.skip_if_press_status(p.second_nibble, Pressed),
-                0xA1 => self.skip_if_press_status(p.second_nibble, Released),
-                _ => {}
-            },
-            0xF => match p.nibbles_3_to_4 {
-                0x07 => self.set_register_to_delay_timer(p.second_nibble),
-                0x15 => self.set_delay_timer_to_register(p.second_nibble),
-                0x18 => self.set_sound_timer_to_register(p.second_nibble),
-                0x1E => self.add_to_index(p.second_nibble),
-                0x0A => self.get_key(p.second_nibble, None),
-                0x29 => self.set_index_register_to_font_character(p.second_nibble),
-                0x33 => self.binary_coded_decimal_conversion(p.second_nibble),
-                0x55 => self.store_registers_to_memory(p.second_nibble),
-                0x65 => self.store_memory_to_registers(p.second_nibble),
-                _ => {}
+        instruction: crate::instruction::Instruction,
+    ) -> Result<(), crate::error::Chip8Error> {
+        use crate::instruction::Instruction;
+
+        match instruction {
+            // machine code routines only existed on the original hardware
+            Instruction::Sys(_) => {}
+            Instruction::MegaOff => self.set_mega_mode(false),
+            Instruction::MegaOn => self.set_mega_mode(true),
+            Instruction::Ldhi(byte) => self.set_index_register_huge(byte)?,
+            Instruction::Ldpal(byte) => self.load_palette(byte as usize)?,
+            Instruction::Sprw(byte) => self.megachip.sprite_width = Self::mega_sprite_size(byte),
+            Instruction::Sprh(byte) => self.megachip.sprite_height = Self::mega_sprite_size(byte),
+            // the screen's opacity is only used for fades, which are not emulated
+            Instruction::Alpha(_) => {}
+            Instruction::Digisnd(n) => self.play_sample(n == 0)?,
+            Instruction::Stopsnd => self.audio.play_sample(self.frames_run, None),
+            Instruction::Bmode(n) => {
+                if let Some(blend_mode) = crate::megachip::BlendMode::from_nibble(n) {
+                    self.megachip.blend_mode = blend_mode;
+                }
+            }
+            Instruction::Ccol(byte) => self.megachip.collision_color = byte,
+            Instruction::Scru(n) => self.scroll_screen(0, -(n as isize)),
+            Instruction::Scd(n) => self.scroll_screen(0, n as isize),
+            Instruction::Scu(n) => self.scroll_screen(0, -(n as isize)),
+            Instruction::Cls => self.clear_screen(),
+            Instruction::Ret => self.stack_return()?,
+            Instruction::Scr => self.scroll_screen(4, 0),
+            Instruction::Scl => self.scroll_screen(-4, 0),
+            Instruction::Exit => self.exit(),
+            Instruction::Low => self.set_high_resolution(false),
+            Instruction::High => self.set_high_resolution(true),
+            // two-page ROMs start with a jump into the interpreter patch they carry at 0x260,
+            // which is emulated, so the program proper starts at 0x2C0
+            Instruction::Jp(0x260)
+                if (self.config.instruction_set
+                    == crate::instruction::InstructionSet::TwoPageHires)
+                    && (self.program_counter == 0x202) =>
+            {
+                self.jump(0x2C0)
+            }
+            Instruction::Jp(address) => self.jump(address as usize),
+            Instruction::Call(address) => self.call_subroutine(address as usize)?,
+            Instruction::SeByte { x, byte } => self.skip_if_register_equals_value(x as usize, byte),
+            Instruction::SneByte { x, byte } => {
+                self.skip_if_register_not_equal_to_value(x as usize, byte)
+            }
+            Instruction::SeReg { x, y } => self.skip_if_registers_equal(x as usize, y as usize),
+            Instruction::SaveRange { x, y } => {
+                self.store_register_range_to_memory(x as usize, y as usize)?
+            }
+            Instruction::LoadRange { x, y } => {
+                self.store_memory_to_register_range(x as usize, y as usize)?
+            }
+            Instruction::LdByte { x, byte } => self.set_register(x as usize, byte),
+            Instruction::AddByte { x, byte } => self.add_to_register(x as usize, byte),
+            Instruction::LdReg { x, y } => self.set_register_to_other(x as usize, y as usize),
+            Instruction::Or { x, y } => self.binary_or(x as usize, y as usize),
+            Instruction::And { x, y } => self.binary_and(x as usize, y as usize),
+            Instruction::Xor { x, y } => self.binary_xor(x as usize, y as usize),
+            Instruction::AddReg { x, y } => self.add_registers(x as usize, y as usize),
+            Instruction::Sub { x, y } => self.subtract_registers(x as usize, y as usize),
+            Instruction::Shr { x, y } => self.right_shift_on_register(x as usize, y as usize),
+            Instruction::Subn { x, y } => self.subtract_registers(y as usize, x as usize),
+            Instruction::Shl { x, y } => self.left_shift_on_register(x as usize, y as usize),
+            Instruction::SneReg { x, y } => {
+                self.skip_if_registers_not_equal(x as usize, y as usize)
+            }
+            Instruction::LdI(address) => self.set_index_register(address as usize),
+            Instruction::JpV0(address) => match self.config.quirks.jump_with_vx {
+                true => self.jump_with_offset(Some((address >> 8) as usize), address as usize),
+                false => self.jump_with_offset(None, address as usize),
             },
-            _ => {}
+            Instruction::Rnd { x, byte } => self.random(x as usize, byte),
+            Instruction::Drw { x, y, n } => {
+                self.draw_to_screen(x as usize, y as usize, n as usize)?
+            }
+            Instruction::Skp(x) => self.skip_if_press_status(x as usize, Pressed)?,
+            Instruction::Sknp(x) => self.skip_if_press_status(x as usize, Released)?,
+            Instruction::SkpSecond(x) => self.skip_if_second_keypad_status(x as usize, Pressed)?,
+            Instruction::SknpSecond(x) => {
+                self.skip_if_second_keypad_status(x as usize, Released)?
+            }
+            Instruction::ClsHires => self.clear_screen(),
+            Instruction::Bgcol => {
+                if let Some(zones) = self.framebuffer.color_zones_mut() {
+                    zones.cycle_background();
+                }
+            }
+            Instruction::Col { x, y, n } => self.color_zones(x as usize, y as usize, n as usize),
+            Instruction::LdILong => self.set_index_register_long()?,
+            Instruction::Plane(n) => self.select_planes(n),
+            Instruction::Audio => self.load_audio_pattern()?,
+            Instruction::Pitch(x) => self.set_pitch_to_register(x as usize),
+            Instruction::LdVxDt(x) => self.set_register_to_delay_timer(x as usize),
+            Instruction::LdVxK(x) => self.get_key(x as usize, None),
+            Instruction::LdDtVx(x) => self.set_delay_timer_to_register(x as usize),
+            Instruction::LdStVx(x) => self.set_sound_timer_to_register(x as usize),
+            Instruction::AddI(x) => self.add_to_index(x as usize),
+            Instruction::LdF(x) => self.set_index_register_to_font_character(x as usize),
+            Instruction::LdHf(x) => self.set_index_register_to_big_font_character(x as usize),
+            Instruction::LdBcd(x) => self.binary_coded_decimal_conversion(x as usize)?,
+            Instruction::LdIVx(x) => self.store_registers_to_memory(x as usize)?,
+            Instruction::LdVxI(x) => self.store_memory_to_registers(x as usize)?,
+            Instruction::LdRVx(x) => self.store_registers_to_flags(x as usize)?,
+            Instruction::LdVxR(x) => self.store_flags_to_registers(x as usize)?,
         }
+        Ok(())
     }
 
     /// Sets the program counter to the provided address.
@@ -371,133 +783,399 @@ impl Emulator {
         self.program_counter = address;
     }
 
+    /// Clears the selected planes of the screen, making all pixels black if every plane is selected.
+    /// In MEGA-CHIP mode the whole screen is cleared.
+    fn clear_screen(&mut self) {
+        if self.megachip.enabled {
+            self.framebuffer.clear();
+        } else {
+            self.framebuffer.clear_planes(self.selected_planes);
+        }
+    }
+
+    /// Selects the planes that drawing, clearing and scrolling affect.
+    fn select_planes(&mut self, planes: u8) {
+        self.selected_planes = planes & crate::framebuffer::ALL_PLANES;
+    }
+
+    /// Scrolls the selected planes right by `dx` and down by `dy` pixels.
+    /// In MEGA-CHIP mode the whole screen scrolls.
+    fn scroll_screen(&mut self, dx: isize, dy: isize) {
+        if self.megachip.enabled {
+            self.framebuffer.scroll(dx, dy);
+        } else {
+            self.framebuffer.scroll_planes(dx, dy, self.selected_planes);
+        }
+    }
+
+    /// Switches between the 64x32 and 128x64 resolutions, clearing the screen.
+    fn set_high_resolution(&mut self, high_resolution: bool) {
+        let (width, height) = if high_resolution {
+            (
+                crate::screen::HIRES_SCREEN_WIDTH,
+                crate::screen::HIRES_SCREEN_HEIGHT,
+            )
+        } else {
+            (crate::screen::SCREEN_WIDTH, crate::screen::SCREEN_HEIGHT)
+        };
+        self.framebuffer.resize(width as usize, height as usize);
+    }
+
+    /// Switches the 256x192 MEGA-CHIP color mode on or off. Switching off returns to the 64x32 resolution.
+    fn set_mega_mode(&mut self, enabled: bool) {
+        self.megachip.enabled = enabled;
+        if enabled {
+            self.framebuffer = crate::framebuffer::Framebuffer::true_color(
+                crate::screen::MEGA_SCREEN_WIDTH as usize,
+                crate::screen::MEGA_SCREEN_HEIGHT as usize,
+            );
+        } else {
+            self.set_high_resolution(false);
+        }
+    }
+
+    /// Whether the screen is in the SUPER-CHIP 128x64 mode.
+    fn is_high_resolution(&self) -> bool {
+        self.framebuffer.width() == crate::screen::HIRES_SCREEN_WIDTH as usize
+    }
+
+    /// Stops the interpreter by staying on the exit instruction, the same way get_key blocks.
+    fn exit(&mut self) {
+        self.program_counter -= 2;
+    }
+
     /// Draws "rows" number of rows of 8 pixels starting from the X and Y coordinates found in register X and register Y, respectively.
+    /// With SUPER-CHIP, drawing 0 rows draws a 16x16 sprite stored as two bytes per row instead.
+    /// With XO-CHIP, one sprite is drawn to each selected plane, read one after the other from the index register.
+    /// Depending on the quirks, sprites are either clipped at the edges of the screen or wrap around to the other side.
     fn draw_to_screen(
         &mut self,
         register_x: usize,
         register_y: usize,
         rows: usize,
-        renderer: &mut crate::renderer::RendererState,
-    ) {
-        let mut y = self.registers[register_y] % 32;
+    ) -> Result<(), crate::error::Chip8Error> {
+        if self.megachip.enabled {
+            return self.draw_color_sprite(register_x, register_y, rows);
+        }
+        let (rows, sprite_width) = if rows == 0
+            && matches!(
+                self.config.instruction_set,
+                crate::instruction::InstructionSet::SuperChip
+                    | crate::instruction::InstructionSet::XoChip
+                    | crate::instruction::InstructionSet::MegaChip
+            ) {
+            (16, 16)
+        } else {
+            (rows, 8)
+        };
+        let sprite_length = rows * sprite_width / 8;
+        let planes: Vec<u8> = [
+            crate::framebuffer::FIRST_PLANE,
+            crate::framebuffer::SECOND_PLANE,
+        ]
+        .into_iter()
+        .filter(|plane| self.selected_planes & plane != 0)
+        .collect();
+        self.check_memory_range(self.index_register as usize, sprite_length * planes.len())?;
+
+        let count_collided_rows =
+            self.config.quirks.count_collided_rows && self.is_high_resolution();
+        let mut collided_rows = 0;
+        for (i, plane) in planes.into_iter().enumerate() {
+            collided_rows += self.draw_sprite_to_plane(
+                (register_x, register_y),
+                self.index_register as usize + i * sprite_length,
+                (sprite_width, rows),
+                plane,
+                count_collided_rows,
+            );
+        }
 
-        self.registers[15] = 0;
+        self.registers[0xF] = if count_collided_rows {
+            collided_rows as u8
+        } else {
+            (collided_rows > 0) as u8
+        };
+        Ok(())
+    }
 
-        'outer: for i in 0..rows {
-            let ith_byte = self.memory[self.index_register as usize + i];
-            let mut x = self.registers[register_x] % 64;
-            'inner: for bit_value in 0..8 {
-                // we subtract bit_value from 8 because bit_range_to_num works from right to left, so we need to flip it
+    /// Draws one sprite to one plane and returns how many of its rows collided.
+    /// Rows clipped at the bottom of the screen count as collided if `count_clipped_rows` is set, as in SUPER-CHIP 1.1.
+    fn draw_sprite_to_plane(
+        &mut self,
+        (register_x, register_y): (usize, usize),
+        sprite_start: usize,
+        (sprite_width, rows): (usize, usize),
+        plane: u8,
+        count_clipped_rows: bool,
+    ) -> usize {
+        let bytes_per_row = sprite_width / 8;
+        let width = self.framebuffer.width();
+        let height = self.framebuffer.height();
+        let start_x = self.registers[register_x] as usize % width;
+        let start_y = self.registers[register_y] as usize % height;
+
+        let mut collided_rows = 0;
+
+        for i in 0..rows {
+            let y = start_y + i;
+            if y >= height && self.config.quirks.clip_sprites {
+                if count_clipped_rows {
+                    collided_rows += rows - i;
+                }
+                break;
+            }
+            let row_start = sprite_start + i * bytes_per_row;
+            let ith_row = self.memory[row_start..row_start + bytes_per_row]
+                .iter()
+                .fold(0, |row, byte| (row << 8) | *byte as u16);
+            let mut row_collided = false;
+            for bit_value in 0..sprite_width {
+                let x = start_x + bit_value;
+                if x >= width && self.config.quirks.clip_sprites {
+                    break;
+                }
+                // we subtract bit_value from the width because bit_range_to_num works from right to left, so we need to flip it
                 let sprite_bit = crate::bit_utils::bit_range_to_num(
-                    ith_byte as u16,
-                    8 - bit_value - 1,
-                    8 - bit_value,
+                    ith_row,
+                    sprite_width - bit_value - 1,
+                    sprite_width - bit_value,
                 )
                 .unwrap();
 
-                // we subtract y from SCREEN_HEIGHT because the pixels are indexed from bottom-left to top-right, so we flip vertically
-                let pixel_index = ((crate::screen::SCREEN_HEIGHT - y as u32)
-                    * crate::screen::SCREEN_WIDTH
-                    + x as u32) as usize;
-                let pixel = &mut renderer.instances[pixel_index];
-
-                if sprite_bit == 1 {
-                    if pixel.color.x > 0.1 {
-                        pixel.color = cgmath::Vector4 {
-                            x: 0.0,
-                            y: 0.0,
-                            z: 0.0,
-                            w: 0.0,
-                        };
-                        self.registers[0xF] = 1;
-                    } else {
-                        pixel.color = cgmath::Vector4 {
-                            x: 1.0,
-                            y: 1.0,
-                            z: 1.0,
-                            w: 0.0,
-                        };
-                    }
+                if (sprite_bit == 1) && self.framebuffer.toggle_plane(x % width, y % height, plane)
+                {
+                    row_collided = true;
                 }
+            }
+            if row_collided {
+                collided_rows += 1;
+            }
+        }
+        collided_rows
+    }
+
+    /// Draws a MEGA-CHIP sprite of the current sprite width and height, one palette index per byte, at (VX, VY).
+    /// Index 0 is transparent and the sprite is clipped at the edges of the screen.
+    /// Font sprites are 1-bit, so their set bits are drawn in the font color instead.
+    /// VF is set if the sprite covers a lit pixel of the collision color.
+    fn draw_color_sprite(
+        &mut self,
+        register_x: usize,
+        register_y: usize,
+        rows: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        let start = self.index_register as usize;
+        // the fonts live below the program, in the interpreter's own memory
+        let (sprite_width, sprite_height, sprite) = if start < self.config.memory.start_address {
+            self.check_memory_range(start, rows)?;
+            let sprite = self.memory[start..start + rows]
+                .iter()
+                .flat_map(|byte| {
+                    (0..8).map(move |bit| {
+                        if byte & (0x80 >> bit) != 0 {
+                            crate::megachip::FONT_COLOR
+                        } else {
+                            0
+                        }
+                    })
+                })
+                .collect();
+            (8, rows, sprite)
+        } else {
+            let (sprite_width, sprite_height) =
+                (self.megachip.sprite_width, self.megachip.sprite_height);
+            self.check_memory_range(start, sprite_width * sprite_height)?;
+            let sprite = self.memory[start..start + sprite_width * sprite_height].to_vec();
+            (sprite_width, sprite_height, sprite)
+        };
 
-                x = x + 1;
-                if x >= crate::screen::SCREEN_WIDTH as u8 {
-                    break 'inner;
+        let start_x = self.registers[register_x] as usize;
+        let start_y = self.registers[register_y] as usize;
+        let width = self.framebuffer.width();
+        let height = self.framebuffer.height();
+        let mut collided = false;
+        for row in 0..sprite_height.min(height.saturating_sub(start_y)) {
+            for column in 0..sprite_width.min(width.saturating_sub(start_x)) {
+                let index = sprite[row * sprite_width + column];
+                if index == 0 {
+                    continue;
                 }
+                let (x, y) = (start_x + column, start_y + row);
+                let under = self.framebuffer.planes(x, y);
+                collided |= under != 0 && under == self.megachip.collision_color;
+                let rgb = self.megachip.blend_mode.blend(
+                    self.framebuffer.color(x, y).unwrap_or_default(),
+                    self.megachip.rgb(index),
+                );
+                self.framebuffer.set_color(x, y, index, rgb);
             }
-            y = y + 1;
-            if y >= crate::screen::SCREEN_HEIGHT as u8 {
-                break 'outer;
-            }
         }
+        self.registers[0xF] = collided as u8;
+        Ok(())
+    }
+
+    /// How many bytes DXYN reads from memory to draw an N-row sprite.
+    fn sprite_length(&self, rows: usize) -> usize {
+        if self.megachip.enabled {
+            return if (self.index_register as usize) < self.config.memory.start_address {
+                rows
+            } else {
+                self.megachip.sprite_width * self.megachip.sprite_height
+            };
+        }
+        let bytes = if rows == 0
+            && matches!(
+                self.config.instruction_set,
+                crate::instruction::InstructionSet::SuperChip
+                    | crate::instruction::InstructionSet::XoChip
+                    | crate::instruction::InstructionSet::MegaChip
+            ) {
+            32
+        } else {
+            rows
+        };
+        bytes * (self.selected_planes & 0b11).count_ones() as usize
+    }
+
+    /// The MEGA-CHIP sprite width or height set by 03NN or 04NN, where 0 stands for 256.
+    fn mega_sprite_size(byte: u8) -> usize {
+        if byte == 0 {
+            256
+        } else {
+            byte as usize
+        }
+    }
+
+    /// Loads `count` ARGB colors from memory at the index register into the MEGA-CHIP palette, starting at index 1.
+    fn load_palette(&mut self, count: usize) -> Result<(), crate::error::Chip8Error> {
+        let start = self.index_register as usize;
+        self.check_memory_range(start, count * 4)?;
+        for (i, color) in self.memory[start..start + count * 4]
+            .chunks_exact(4)
+            .enumerate()
+        {
+            self.megachip.palette[i + 1].copy_from_slice(color);
+        }
+        Ok(())
+    }
+
+    /// Plays the MEGA-CHIP digitized sound at the index register.
+    fn play_sample(&mut self, looping: bool) -> Result<(), crate::error::Chip8Error> {
+        let start = self.index_register as usize;
+        let sample =
+            crate::megachip::Sample::parse(self.memory.get(start..).unwrap_or_default(), looping)
+                .ok_or(crate::error::Chip8Error::AddressOutOfRange {
+                address: start.max(self.memory.len()),
+            })?;
+        self.audio.play_sample(self.frames_run, Some(sample));
+        Ok(())
     }
 
     /// Sets register X to the provided value.
-    fn set_register(&mut self, register_x: usize, value: u8) {
+    pub fn set_register(&mut self, register_x: usize, value: u8) {
         self.registers[register_x] = value;
     }
 
     /// Adds the provided addend to register X.
     fn add_to_register(&mut self, register_x: usize, addend: u8) {
         let register_to_change = &mut self.registers[register_x];
-        let sum = (*register_to_change as u16 + addend as u16) % (std::u8::MAX as u16 + 1);
+        let sum = (*register_to_change as u16 + addend as u16) % (u8::MAX as u16 + 1);
         *register_to_change = sum as u8;
     }
 
     /// Sets the index register to the provided address.
-    fn set_index_register(&mut self, address: usize) {
-        self.index_register = address as u16;
-    }
-
-    /// Loads memory from the provided ROM.
-    fn load_memory_from_rom(file_path: &str) -> [u8; MEMORY_SIZE] {
-        let mut memory = [0; MEMORY_SIZE];
-
-        let rom_contents = std::fs::read(file_path).unwrap();
-        for (i, instruction) in rom_contents.iter().enumerate() {
-            memory[0x200 + i] = *instruction;
+    pub fn set_index_register(&mut self, address: usize) {
+        self.index_register = address as u32;
+    }
+
+    /// Sets the index register to the 16-bit address following the instruction and skips over it.
+    fn set_index_register_long(&mut self) -> Result<(), crate::error::Chip8Error> {
+        self.index_register = self.get_opcode_from_memory()? as u32;
+        self.program_counter += 2;
+        Ok(())
+    }
+
+    /// Sets the index register to the 24-bit address made of `high` and the 16 bits following the instruction, and skips over them.
+    fn set_index_register_huge(&mut self, high: u8) -> Result<(), crate::error::Chip8Error> {
+        self.index_register = (high as u32) << 16 | self.get_opcode_from_memory()? as u32;
+        self.program_counter += 2;
+        Ok(())
+    }
+
+    /// Skips over the next instruction, which is 4 bytes long if it is XO-CHIP's F000 NNNN or MEGA-CHIP's 01NN NNNN.
+    fn skip_next_instruction(&mut self) {
+        let length = self
+            .current_instruction()
+            .map_or(2, |instruction| instruction.length());
+        self.program_counter += length as usize;
+    }
+
+    /// Loads the provided ROM into memory at the start address. Returns the ROM's hash.
+    fn load_memory_from_rom(
+        rom_contents: &[u8],
+        memory: &mut [u8],
+        start_address: usize,
+    ) -> Result<u64, crate::error::Chip8Error> {
+        let max_size = memory.len() - start_address;
+        if rom_contents.len() > max_size {
+            return Err(crate::error::Chip8Error::RomTooLarge {
+                size: rom_contents.len(),
+                max_size,
+            });
         }
+        memory[start_address..start_address + rom_contents.len()].clone_from_slice(rom_contents);
 
-        memory
+        Ok(crate::flags::rom_hash(rom_contents))
     }
 
-    /// Sets the program coutner to the top of the stack and pops from the stack.
-    fn stack_return(&mut self) {
-        self.program_counter = *self.stack.top().unwrap() as usize;
-        self.stack.pop();
+    /// Sets the program counter to the top of the stack and pops from the stack.
+    fn stack_return(&mut self) -> Result<(), crate::error::Chip8Error> {
+        self.program_counter =
+            self.stack
+                .pop()
+                .map_err(|_| crate::error::Chip8Error::StackUnderflow {
+                    address: self.program_counter.saturating_sub(2),
+                })?;
+        Ok(())
     }
 
     /// Pushes the current program counter to the stack and sets the program counter to address.
-    fn call_subroutine(&mut self, address: usize) {
-        self.stack.push(self.program_counter);
+    fn call_subroutine(&mut self, address: usize) -> Result<(), crate::error::Chip8Error> {
+        self.stack.push(self.program_counter).map_err(|_| {
+            crate::error::Chip8Error::StackOverflow {
+                address: self.program_counter.saturating_sub(2),
+            }
+        })?;
         self.program_counter = address;
+        Ok(())
     }
 
     /// Skips the next instruction if register X equals the provided value.
     fn skip_if_register_equals_value(&mut self, register_x: usize, value: u8) {
         if self.registers[register_x] == value {
-            self.program_counter = self.program_counter + 2;
+            self.skip_next_instruction();
         }
     }
 
     /// Skips the next instruction if register X does not equal the provided value.
     fn skip_if_register_not_equal_to_value(&mut self, register_x: usize, value: u8) {
         if self.registers[register_x] != value {
-            self.program_counter = self.program_counter + 2;
+            self.skip_next_instruction();
         }
     }
 
     /// Skips the next instruction if registers X and Y are equal.
     fn skip_if_registers_equal(&mut self, register_x: usize, register_y: usize) {
         if self.registers[register_x] == self.registers[register_y] {
-            self.program_counter = self.program_counter + 2;
+            self.skip_next_instruction();
         }
     }
 
     /// Skips the next instruction if registers X and Y are not equal.
     fn skip_if_registers_not_equal(&mut self, register_x: usize, register_y: usize) {
         if self.registers[register_x] != self.registers[register_y] {
-            self.program_counter = self.program_counter + 2;
+            self.skip_next_instruction();
         }
     }
 
@@ -508,24 +1186,34 @@ impl Emulator {
 
     /// Sets register X to the bitwise/binary logical disjunction (OR) of register X and register Y.
     fn binary_or(&mut self, register_x: usize, register_y: usize) {
-        self.registers[register_x] = self.registers[register_x] | self.registers[register_y];
+        self.registers[register_x] |= self.registers[register_y];
+        self.reset_register_f();
     }
 
     /// Sets register X to the bitwise/binary logical conjunction (AND) of register X and register Y.
     fn binary_and(&mut self, register_x: usize, register_y: usize) {
-        self.registers[register_x] = self.registers[register_x] & self.registers[register_y];
+        self.registers[register_x] &= self.registers[register_y];
+        self.reset_register_f();
     }
 
     /// Sets register X to the bitwise/binary logical exclusive OR (XOR) of register X and register Y.
     fn binary_xor(&mut self, register_x: usize, register_y: usize) {
-        self.registers[register_x] = self.registers[register_x] ^ self.registers[register_y];
+        self.registers[register_x] ^= self.registers[register_y];
+        self.reset_register_f();
+    }
+
+    /// Sets register F to 0 after a logical operation if the VF reset quirk is enabled.
+    fn reset_register_f(&mut self) {
+        if self.config.quirks.vf_reset {
+            self.registers[0xF] = 0;
+        }
     }
 
     /// Sets register X to the sum of register X and register Y.
     /// Sets register F to 1 if result > 255, otherwise sets it to 0.
     fn add_registers(&mut self, register_x: usize, register_y: usize) {
         let temp: u16 = self.registers[register_x] as u16 + self.registers[register_y] as u16;
-        let u8_max_cast = std::u8::MAX as u16;
+        let u8_max_cast = u8::MAX as u16;
         if temp > u8_max_cast {
             self.registers[0xF] = 1;
         } else {
@@ -542,45 +1230,42 @@ impl Emulator {
         let carry: u8 = if temp < 0 { 0 } else { 1 };
         let borrowed: i16 = if carry == 0 { 1 } else { 0 };
         self.registers[0xF] = carry;
-        self.registers[left_register] = (temp + ((std::u8::MAX as i16 + 1) * borrowed)) as u8;
+        self.registers[left_register] = (temp + ((u8::MAX as i16 + 1) * borrowed)) as u8;
     }
 
     /// Shifts register X by 1 bit to the right and sets register F to the bit shifted out.
-    /// Sets register X to register Y first if the emulator has COSMAC VIP instructions.
+    /// Sets register X to register Y first if the shift uses register Y (as on the COSMAC VIP).
     fn right_shift_on_register(&mut self, register_x: usize, register_y: usize) {
-        self.registers[register_x] = if self.has_cosmac_vip_instructions {
+        self.registers[register_x] = if self.config.quirks.shift_uses_vy {
             self.registers[register_y]
         } else {
             self.registers[register_x]
         };
         self.registers[0xF] = self.registers[register_x] & 1;
-        self.registers[register_x] = self.registers[register_x] >> 1;
+        self.registers[register_x] >>= 1;
     }
 
     /// Shifts register X by 1 bit to the left and sets register F to the bit shifted out.
-    /// Sets register X to register Y first if the emulator has COSMAC VIP instructions.
+    /// Sets register X to register Y first if the shift uses register Y (as on the COSMAC VIP).
     fn left_shift_on_register(&mut self, register_x: usize, register_y: usize) {
-        self.registers[register_x] = if self.has_cosmac_vip_instructions {
+        self.registers[register_x] = if self.config.quirks.shift_uses_vy {
             self.registers[register_y]
         } else {
             self.registers[register_x]
         };
         self.registers[0xF] = (self.registers[register_x] & 0b10000000) >> 7;
-        self.registers[register_x] = self.registers[register_x] << 1;
+        self.registers[register_x] <<= 1;
     }
 
     /// Sets the program counter to address + the value in register X (or register 0 if a COSMAC VIP).
     fn jump_with_offset(&mut self, register_x: Option<usize>, address: usize) {
-        let register_to_use = match register_x {
-            Some(register) => register,
-            None => 0,
-        };
+        let register_to_use = register_x.unwrap_or_default();
         self.program_counter = address + self.registers[register_to_use] as usize;
     }
 
-    /// Generates a rendom number and binary ANDs it with and_value.
+    /// Generates a random number and binary ANDs it with and_value.
     fn random(&mut self, register_x: usize, and_value: u8) {
-        self.registers[register_x] = rand::random::<u8>() & and_value;
+        self.registers[register_x] = self.rng.next_byte(&self.memory) & and_value;
     }
 
     /// Skips one instruction if the key corresponding to the value in register X is equal to press_status
@@ -588,21 +1273,89 @@ impl Emulator {
         &mut self,
         register_x: usize,
         press_status: winit::event::ElementState,
-    ) {
-        if *self
-            .pressed
-            .get(
-                self.pressed_hex_map
-                    .get_by_left(&(self.registers[register_x]))
-                    .unwrap(),
-            )
-            .unwrap()
-            == press_status
-        {
-            self.program_counter = self.program_counter + 2;
+    ) -> Result<(), crate::error::Chip8Error> {
+        let key = self.registers[register_x];
+        let scancode = self
+            .pressed_hex_map
+            .get_by_left(&key)
+            .ok_or(crate::error::Chip8Error::InvalidKey { key })?;
+        if self.pressed[scancode] == press_status {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// Skips the next instruction if the key in register X has the press status on the CHIP-8X second keypad.
+    fn skip_if_second_keypad_status(
+        &mut self,
+        register_x: usize,
+        press_status: winit::event::ElementState,
+    ) -> Result<(), crate::error::Chip8Error> {
+        let key = self.registers[register_x];
+        let scancode = self
+            .second_pressed_hex_map
+            .get_by_left(&key)
+            .ok_or(crate::error::Chip8Error::InvalidKey { key })?;
+        if self.pressed[scancode] == press_status {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// Sets CHIP-8X foreground colors. With N = 0, the low and high nibbles of VX are the first 8-pixel column and how many more to color,
+    /// those of VX+1 are the first 4-pixel zone row and how many more to color, and VY is the color.
+    /// Otherwise N rows of the column holding (VX, VY) take the color in VX+1.
+    fn color_zones(&mut self, register_x: usize, register_y: usize, rows: usize) {
+        let x = self.registers[register_x] as usize;
+        let next = self.registers[(register_x + 1) % 16] as usize;
+        let Some(zones) = self.framebuffer.color_zones_mut() else {
+            return;
+        };
+        if rows == 0 {
+            let (column, row) = (x & 0xF, (next & 0xF) * crate::chip8x::ZONE_HEIGHT);
+            let (columns, zone_rows) = ((x >> 4) + 1, (next >> 4) + 1);
+            zones.set_foreground(
+                column..column + columns,
+                row..row + zone_rows * crate::chip8x::ZONE_HEIGHT,
+                self.registers[register_y],
+            );
+        } else {
+            let column = x / crate::chip8x::ZONE_WIDTH;
+            let y = self.registers[register_y] as usize;
+            zones.set_foreground(column..column + 1, y..y + rows, next as u8);
         }
     }
 
+    /// Loads the 16-byte audio pattern at the index register, which the buzzer then plays.
+    fn load_audio_pattern(&mut self) -> Result<(), crate::error::Chip8Error> {
+        let start = self.index_register as usize;
+        self.check_memory_range(start, 16)?;
+        self.audio_pattern = Some(
+            self.memory[start..start + 16]
+                .try_into()
+                .expect("Expected to receive 16 values from memory"),
+        );
+        self.send_audio_pattern();
+        Ok(())
+    }
+
+    /// Sets the audio pattern's playback pitch to the value in register X.
+    fn set_pitch_to_register(&mut self, register_x: usize) {
+        self.pitch = self.registers[register_x];
+        self.send_audio_pattern();
+    }
+
+    /// Tells the audio sink about the current audio pattern and pitch.
+    fn send_audio_pattern(&mut self) {
+        let pattern = self
+            .audio_pattern
+            .map(|pattern| crate::buzzer::AudioPattern {
+                pattern,
+                pitch: self.pitch,
+            });
+        self.audio.set_pattern(self.frames_run, pattern);
+    }
+
     /// Sets register X to the current value of the delay timer.
     fn set_register_to_delay_timer(&mut self, register_x: usize) {
         self.registers[register_x] = self.delay_timer.counter;
@@ -621,13 +1374,14 @@ impl Emulator {
     /// Adds register X to the index register. Will set register F to 1 if the index register overflows.
     /// While this behavior is inconsistent between emulators, it appears that at least one game relies on
     /// setting register F to 1, whereas no games don't rely on it, so we opt for this standardized approach.
+    /// The index register is 16 bits wide, or 24 bits with MEGA-CHIP.
     fn add_to_index(&mut self, register_x: usize) {
-        let temp: u32 = self.index_register as u32 + self.registers[register_x] as u32;
-        let u16_max_cast = std::u16::MAX as u32;
-        if temp > u16_max_cast {
+        let temp: u32 = self.index_register + self.registers[register_x] as u32;
+        let address_space = (self.memory.len() as u32).max(u16::MAX as u32 + 1);
+        if temp >= address_space {
             self.registers[0xF] = 1;
         }
-        self.index_register = (temp % (u16_max_cast + 1)) as u16;
+        self.index_register = temp % address_space;
     }
 
     /// Blocks execution and waits for key input, but the timers should still be decreased while waiting.
@@ -638,8 +1392,9 @@ impl Emulator {
         match new_key_pressed {
             Some(scancode) => {
                 self.registers[register_x] = *self.pressed_hex_map.get_by_right(&scancode).unwrap();
+                self.program_counter += 2;
             }
-            None => self.program_counter = self.program_counter - 2,
+            None => self.program_counter -= 2,
         }
     }
 
@@ -647,13 +1402,25 @@ impl Emulator {
     fn set_index_register_to_font_character(&mut self, register_x: usize) {
         let last_nibble =
             crate::bit_utils::bit_range_to_num(self.registers[register_x].into(), 0, 4).unwrap();
-        self.index_register = FONT_MEMORY_START as u16 + 5 * last_nibble;
+        self.index_register = self.config.memory.font_address as u32 + 5 * last_nibble as u32;
+    }
+
+    /// Sets the index register to the address of the 10-byte big font character represented by the last nibble in register X.
+    fn set_index_register_to_big_font_character(&mut self, register_x: usize) {
+        let last_nibble =
+            crate::bit_utils::bit_range_to_num(self.registers[register_x].into(), 0, 4).unwrap();
+        self.index_register =
+            self.config.memory.big_font_address() as u32 + 10 * last_nibble as u32;
     }
 
     /// Takes the number in register X, converts it to three decimal digits, and stores the digits in memory at addresses starting with the index register.
     /// For example: 254 gets stored in memory[index_register..index_register+3] as [2, 5, 4]
-    fn binary_coded_decimal_conversion(&mut self, register_x: usize) {
+    fn binary_coded_decimal_conversion(
+        &mut self,
+        register_x: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
         let casted_index_register = self.index_register as usize;
+        self.check_memory_range(casted_index_register, 3)?;
         let converted_number = self.registers[register_x];
 
         let hundreds_place = converted_number / 100;
@@ -663,40 +1430,121 @@ impl Emulator {
         self.memory[casted_index_register] = hundreds_place;
         self.memory[casted_index_register + 1] = tens_place;
         self.memory[casted_index_register + 2] = ones_place;
+        Ok(())
     }
 
     /// Stores all register values from 0 to X to the memory starting at the index register.
-    /// The COSMAC VIP incremented the index register while this function ran.
-    fn store_registers_to_memory(&mut self, register_x: usize) {
+    /// The COSMAC VIP and some later interpreters incremented the index register while this function ran.
+    fn store_registers_to_memory(
+        &mut self,
+        register_x: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        self.check_memory_range(self.index_register as usize, register_x + 1)?;
         for register in 0..register_x + 1 {
             self.memory[self.index_register as usize + register] = self.registers[register];
         }
-        if self.has_cosmac_vip_instructions {
-            self.index_register = self.index_register + register_x as u16 + 1;
+        self.increment_index_after_memory_access(register_x);
+        Ok(())
+    }
+
+    /// Advances the index register after FX55 or FX65 according to the memory increment quirk.
+    fn increment_index_after_memory_access(&mut self, register_x: usize) {
+        match self.config.quirks.memory_increment {
+            crate::quirks::MemoryIncrement::None => {}
+            crate::quirks::MemoryIncrement::X => self.index_register += register_x as u32,
+            crate::quirks::MemoryIncrement::XPlusOne => {
+                self.index_register += register_x as u32 + 1
+            }
         }
     }
 
     /// Takes sequential values from memory starting at the index register and loads them to registers 0 to X.
-    /// The COSCMAC VIP incremented the index register while this function ran.
-    fn store_memory_to_registers(&mut self, register_x: usize) {
+    /// The COSMAC VIP and some later interpreters incremented the index register while this function ran.
+    fn store_memory_to_registers(
+        &mut self,
+        register_x: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        self.check_memory_range(self.index_register as usize, register_x + 1)?;
         for register in 0..register_x + 1 {
             self.registers[register] = self.memory[self.index_register as usize + register];
         }
-        if self.has_cosmac_vip_instructions {
-            self.index_register = self.index_register + register_x as u16 + 1;
+        self.increment_index_after_memory_access(register_x);
+        Ok(())
+    }
+
+    /// Stores registers X to Y in memory starting at the index register, in reverse order if X > Y.
+    /// Unlike FX55, the index register is left unchanged.
+    fn store_register_range_to_memory(
+        &mut self,
+        register_x: usize,
+        register_y: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        let registers = Self::register_range(register_x, register_y);
+        self.check_memory_range(self.index_register as usize, registers.len())?;
+        for (offset, register) in registers.into_iter().enumerate() {
+            self.memory[self.index_register as usize + offset] = self.registers[register];
+        }
+        Ok(())
+    }
+
+    /// Loads registers X to Y from memory starting at the index register, in reverse order if X > Y.
+    /// Unlike FX65, the index register is left unchanged.
+    fn store_memory_to_register_range(
+        &mut self,
+        register_x: usize,
+        register_y: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        let registers = Self::register_range(register_x, register_y);
+        self.check_memory_range(self.index_register as usize, registers.len())?;
+        for (offset, register) in registers.into_iter().enumerate() {
+            self.registers[register] = self.memory[self.index_register as usize + offset];
         }
+        Ok(())
     }
-}
 
-/// Clears the screen, making all pixels black.
-fn clear_screen(renderer: &mut crate::renderer::RendererState) {
-    for instance in renderer.instances.iter_mut() {
-        instance.color = cgmath::Vector4 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            w: 0.0,
-        };
+    /// The registers from X to Y inclusive, counting down if X > Y.
+    fn register_range(register_x: usize, register_y: usize) -> Vec<usize> {
+        if register_x <= register_y {
+            (register_x..=register_y).collect()
+        } else {
+            (register_y..=register_x).rev().collect()
+        }
+    }
+
+    /// Ensures registers 0 to X fit in the RPL user flags of this instruction set.
+    fn check_flag_range(&self, register_x: usize) -> Result<(), crate::error::Chip8Error> {
+        let flag_count = self.config.instruction_set.flag_count();
+        if register_x >= flag_count {
+            return Err(crate::error::Chip8Error::FlagOutOfRange {
+                register: register_x as u8,
+                flag_count,
+            });
+        }
+        Ok(())
+    }
+
+    /// Saves registers 0 to X to the RPL user flags, which persist across restarts.
+    /// A store that cannot be written only loses the flags at exit, so the ROM keeps running.
+    fn store_registers_to_flags(
+        &mut self,
+        register_x: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        self.check_flag_range(register_x)?;
+        self.flags[..register_x + 1].clone_from_slice(&self.registers[..register_x + 1]);
+        if let Err(error) = self.flag_store.save(self.rom_hash, &self.flags) {
+            log::warn!("{}", error);
+        }
+        Ok(())
+    }
+
+    /// Loads registers 0 to X from the RPL user flags.
+    fn store_flags_to_registers(
+        &mut self,
+        register_x: usize,
+    ) -> Result<(), crate::error::Chip8Error> {
+        self.check_flag_range(register_x)?;
+        self.registers[..register_x + 1].clone_from_slice(&self.flags[..register_x + 1]);
+        Ok(())
     }
 }
 
@@ -707,25 +1555,124 @@ fn clear_screen(renderer: &mut crate::renderer::RendererState) {
 #[cfg(test)]
 mod emulator_tests {
     use super::Emulator;
+    use crate::audio::{BuzzerTransition, CaptureSink, PatternChange, SampleChange};
+    use crate::buzzer::AudioPattern;
+    use crate::config::EmulatorConfig;
+    use crate::error::Chip8Error;
+    use crate::flags::MemoryFlagStore;
+    use crate::instruction::InstructionSet;
+    use crate::megachip::Sample;
+    use crate::platform::{InitialMemory, Platform};
+    use crate::quirks::Quirks;
     use winit::event::ElementState::{Pressed, Released};
 
     #[tokio::test]
     async fn test_jump() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.jump(0x210);
         assert_eq!(emulator.program_counter, 0x210);
     }
 
     #[tokio::test]
-    async fn test_set_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+    async fn test_draw_to_screen() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.index_register = 0x300;
+        emulator.memory[0x300] = 0b11000000;
+        emulator.memory[0x301] = 0b10000000;
+        emulator.registers[0] = 62;
+        emulator.registers[1] = 31;
+        emulator.draw_to_screen(0, 1, 2).unwrap();
+        assert!(emulator.framebuffer.get(62, 31));
+        assert!(emulator.framebuffer.get(63, 31));
+        assert_eq!(emulator.registers[0xF], 0);
+
+        emulator.draw_to_screen(0, 1, 1).unwrap();
+        assert!(!emulator.framebuffer.get(62, 31));
+        assert_eq!(emulator.registers[0xF], 1);
+    }
+
+    #[tokio::test]
+    async fn test_clear_screen() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.index_register = 0x50;
+        emulator.draw_to_screen(0, 0, 5).unwrap();
+        emulator.clear_screen();
+        assert!(emulator
+            .framebuffer
+            .pixels()
+            .iter()
+            .all(|pixel| *pixel == 0));
+    }
+
+    #[tokio::test]
+    async fn test_draw_to_screen_wraps() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::XO_CHIP))
+            .await
+            .unwrap();
+        emulator.index_register = 0x300;
+        emulator.memory[0x300] = 0b11000000;
+        emulator.memory[0x301] = 0b10000000;
+        emulator.registers[0] = 63;
+        emulator.registers[1] = 31;
+        emulator.draw_to_screen(0, 1, 2).unwrap();
+        assert!(emulator.framebuffer.get(63, 31));
+        assert!(emulator.framebuffer.get(0, 31));
+        assert!(emulator.framebuffer.get(63, 0));
+    }
+
+    #[tokio::test]
+    async fn test_instructions_per_frame() {
+        let mut config = EmulatorConfig::new(Quirks::SCHIP_MODERN);
+        config.instructions_per_frame = 100;
+        let mut emulator = Emulator::new(None, config).await.unwrap();
+        // loop forever adding 1 to register 0
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x70, 0x01, 0x12, 0x00]);
+        emulator.delay_timer.counter = 10;
+        assert_eq!(emulator.run_frame().unwrap().instructions_executed, 100);
+
+        emulator.set_instructions_per_frame(7);
+        assert_eq!(emulator.run_frame().unwrap().instructions_executed, 7);
+        assert_eq!(emulator.delay_timer.counter, 8);
+    }
+
+    #[tokio::test]
+    async fn test_display_wait() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        // draw, then jump back to the draw
+        emulator.memory[0x200..0x204].clone_from_slice(&[0xD0, 0x01, 0x12, 0x00]);
+        let outcome = emulator.run_frame().unwrap();
+        assert_eq!(outcome.instructions_executed, 1);
+
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
+        emulator.memory[0x200..0x204].clone_from_slice(&[0xD0, 0x01, 0x12, 0x00]);
+        let outcome = emulator.run_frame().unwrap();
+        assert_eq!(outcome.instructions_executed, 12);
+    }
+
+    #[tokio::test]
+    async fn test_set_register() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.set_register(12, 41);
         assert_eq!(emulator.registers[12], 41);
     }
 
     #[tokio::test]
     async fn test_add_to_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[11] = 0;
         emulator.add_to_register(11, 15);
         assert_eq!(emulator.registers[11], 15);
@@ -736,33 +1683,59 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_set_index_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.set_index_register(1411);
         assert_eq!(emulator.index_register, 1411);
     }
 
     #[tokio::test]
     async fn test_stack_return() {
-        let mut emulator = Emulator::new(None, true, false).await;
-        emulator.stack.push(100);
-        emulator.stack.push(200);
-        emulator.stack_return();
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.stack.push(100).unwrap();
+        emulator.stack.push(200).unwrap();
+        emulator.stack_return().unwrap();
         assert_eq!(emulator.program_counter, 200);
         assert_eq!(*emulator.stack.top().unwrap(), 100);
     }
 
     #[tokio::test]
     async fn test_call_subroutine() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
-        emulator.call_subroutine(400);
+        emulator.call_subroutine(400).unwrap();
         assert_eq!(*emulator.stack.top().unwrap(), 200);
         assert_eq!(emulator.program_counter, 400);
     }
 
+    #[tokio::test]
+    async fn test_stack_overflow() {
+        let mut config = EmulatorConfig::new(Quirks::COSMAC_VIP);
+        config.stack_depth = crate::stack::COSMAC_VIP_DEPTH;
+        let mut emulator = Emulator::new(None, config).await.unwrap();
+        // a subroutine that calls itself forever
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x22, 0x00]);
+        for _ in 0..12 {
+            emulator.step().unwrap();
+        }
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::StackOverflow { address: 0x200 })
+        );
+        assert_eq!(emulator.stack().len(), 12);
+        assert!(emulator.stack().iter().all(|address| *address == 0x202));
+    }
+
     #[tokio::test]
     async fn test_skip_if_register_equals_value() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[0] = 1;
         emulator.skip_if_register_equals_value(0, 1);
@@ -773,7 +1746,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_skip_if_register_not_equal_to_value() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[0] = 1;
         emulator.skip_if_register_not_equal_to_value(0, 2);
@@ -784,7 +1759,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_skip_if_registers_equal() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[0] = 1;
         emulator.registers[1] = 1;
@@ -797,7 +1774,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_skip_if_registers_not_equal() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[0] = 1;
         emulator.registers[1] = 2;
@@ -810,7 +1789,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_set_register_to_other() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 1;
         emulator.registers[1] = 15;
         emulator.set_register_to_other(0, 1);
@@ -819,7 +1800,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_binary_or() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 0b1000;
         emulator.registers[1] = 0b0111;
         emulator.binary_or(0, 1);
@@ -829,11 +1812,24 @@ mod emulator_tests {
         emulator.registers[3] = 0b0011;
         emulator.binary_or(2, 3);
         assert_eq!(emulator.registers[2], 0b1011);
+
+        emulator.registers[0xF] = 1;
+        emulator.binary_or(2, 3);
+        assert_eq!(emulator.registers[0xF], 0);
+
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
+        emulator.registers[0xF] = 1;
+        emulator.binary_or(2, 3);
+        assert_eq!(emulator.registers[0xF], 1);
     }
 
     #[tokio::test]
     async fn test_binary_and() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 0b1000;
         emulator.registers[1] = 0b1011;
         emulator.binary_and(0, 1);
@@ -847,7 +1843,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_binary_xor() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 0b0110;
         emulator.registers[1] = 0b1101;
         emulator.binary_xor(0, 1);
@@ -861,7 +1859,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_add_registers() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 100;
         emulator.registers[1] = 50;
         emulator.add_registers(0, 1);
@@ -882,7 +1882,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_subtract_registers() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 100;
         emulator.registers[1] = 40;
         emulator.subtract_registers(0, 1);
@@ -898,7 +1900,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_right_shift_on_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
 
         emulator.registers[0] = 0b100;
         emulator.registers[1] = 0b101;
@@ -912,7 +1916,9 @@ mod emulator_tests {
         assert_eq!(emulator.registers[0], 0b100);
         assert_eq!(emulator.registers[15], 0);
 
-        emulator = Emulator::new(None, true, false).await;
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
 
         emulator.registers[0] = 0b1000;
         emulator.registers[1] = 0b110110;
@@ -929,7 +1935,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_left_shift_on_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
 
         emulator.registers[0] = 0b100;
         emulator.registers[1] = 0b101;
@@ -943,7 +1951,9 @@ mod emulator_tests {
         assert_eq!(emulator.registers[0], 0b00011010);
         assert_eq!(emulator.registers[15], 1);
 
-        emulator = Emulator::new(None, true, false).await;
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
 
         emulator.registers[0] = 0b10001;
         emulator.registers[1] = 0b111010;
@@ -960,44 +1970,93 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_jump_with_offset() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[0] = 10;
         emulator.jump_with_offset(None, 100);
         assert_eq!(emulator.program_counter, 110);
 
-        emulator = Emulator::new(None, true, false).await;
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[4] = 10;
         emulator.jump_with_offset(Some(4), 1000);
         assert_eq!(emulator.program_counter, 1010);
     }
 
+    #[tokio::test]
+    async fn test_random_is_seeded() {
+        let mut config = EmulatorConfig::new(Quirks::SCHIP_MODERN);
+        config.rng_seed = Some(42);
+        let mut left = Emulator::new(None, config).await.unwrap();
+        let mut right = Emulator::new(None, config).await.unwrap();
+        for register in 0..16 {
+            left.random(register, 0xFF);
+            right.random(register, 0xFF);
+        }
+        assert_eq!(left.registers, right.registers);
+
+        left.random(0, 0x0F);
+        assert_eq!(left.registers[0] & 0xF0, 0);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_state() {
+        let mut config = EmulatorConfig::new(Quirks::SCHIP_MODERN);
+        config.rng_seed = Some(7);
+        let mut emulator = Emulator::new(None, config).await.unwrap();
+        // call a subroutine that sets a random register and draws it
+        emulator.memory[0x200..0x208]
+            .clone_from_slice(&[0x22, 0x04, 0x00, 0x00, 0xC0, 0xFF, 0xD0, 0x05]);
+        emulator.step().unwrap();
+        let state = emulator.save_state();
+
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        let registers = emulator.registers;
+        let framebuffer = emulator.framebuffer.clone();
+
+        emulator.load_state(&state);
+        assert_eq!(emulator.program_counter, 0x204);
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        assert_eq!(emulator.registers, registers);
+        assert_eq!(emulator.framebuffer, framebuffer);
+        assert_eq!(emulator.save_state().stack, state.stack);
+    }
+
     #[tokio::test]
     async fn test_skip_if_press_status() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 200;
         emulator.registers[0] = 0x2;
         *emulator.pressed.get_mut(&3).unwrap() = Pressed;
-        emulator.skip_if_press_status(0, Pressed);
+        emulator.skip_if_press_status(0, Pressed).unwrap();
         assert_eq!(emulator.program_counter, 202);
 
         emulator.program_counter = 200;
         emulator.registers[1] = 0xE;
         *emulator.pressed.get_mut(&34).unwrap() = Pressed;
-        emulator.skip_if_press_status(1, Pressed);
+        emulator.skip_if_press_status(1, Pressed).unwrap();
         assert_eq!(emulator.program_counter, 202);
 
         emulator.program_counter = 200;
         emulator.registers[2] = 0xF;
         *emulator.pressed.get_mut(&49).unwrap() = Released;
-        emulator.skip_if_press_status(2, Released);
+        emulator.skip_if_press_status(2, Released).unwrap();
         assert_eq!(emulator.program_counter, 202);
     }
 
     #[tokio::test]
     async fn test_set_register_to_delay_timer() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.delay_timer.counter = 100;
         emulator.set_register_to_delay_timer(1);
         assert_eq!(emulator.registers[1], 100);
@@ -1005,7 +2064,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_set_delay_timer_to_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[1] = 100;
         emulator.set_delay_timer_to_register(1);
         assert_eq!(emulator.delay_timer.counter, 100);
@@ -1013,7 +2074,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_set_sound_timer_to_register() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[1] = 100;
         emulator.set_sound_timer_to_register(1);
         assert_eq!(emulator.sound_timer.counter, 100);
@@ -1021,14 +2084,16 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_add_to_index() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.index_register = 100;
         emulator.registers[1] = 21;
         emulator.add_to_index(1);
         assert_eq!(emulator.registers[0xF], 0);
         assert_eq!(emulator.index_register, 121);
 
-        emulator.index_register = std::u16::MAX;
+        emulator.index_register = u16::MAX as u32;
         emulator.registers[1] = 11;
         emulator.add_to_index(1);
         assert_eq!(emulator.registers[0xF], 1);
@@ -1037,7 +2102,9 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_get_key() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.program_counter = 102;
         emulator.get_key(1, None);
         assert_eq!(emulator.program_counter, 100);
@@ -1048,14 +2115,798 @@ mod emulator_tests {
             emulator.registers[1],
             *emulator.pressed_hex_map.get_by_right(&3).unwrap()
         );
+        assert_eq!(emulator.program_counter, 102);
+    }
+
+    #[tokio::test]
+    async fn test_step() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
+        emulator.memory[0x200..0x206].clone_from_slice(&[0x60, 0x05, 0xD0, 0x01, 0xF2, 0x0A]);
+
+        let outcome = emulator.step().unwrap();
+        assert_eq!(outcome.opcode, 0x6005);
+        assert!(!outcome.screen_changed);
+        assert_eq!(emulator.registers[0], 5);
+        assert_eq!(emulator.program_counter, 0x202);
+
+        let outcome = emulator.step().unwrap();
+        assert_eq!(outcome.opcode, 0xD001);
+        assert!(outcome.screen_changed);
+
+        let outcome = emulator.step().unwrap();
+        assert!(outcome.waiting_for_key);
+        assert_eq!(emulator.program_counter, 0x204);
+
+        emulator.handle_input(&Pressed, &4);
+        assert_eq!(emulator.registers[2], 0x3);
+        assert!(!emulator.is_waiting_for_key());
+        assert_eq!(emulator.program_counter, 0x206);
+    }
+
+    #[tokio::test]
+    async fn test_step_errors() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x00, 0xEE]);
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::StackUnderflow { address: 0x200 })
+        );
+        assert_eq!(emulator.program_counter, 0x200);
+
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x51, 0x21]);
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::UnknownOpcode {
+                opcode: 0x5121,
+                address: 0x200
+            })
+        );
+
+        emulator.memory[0x200..0x202].clone_from_slice(&[0xE0, 0x9E]);
+        emulator.registers[0] = 0x10;
+        assert_eq!(emulator.step(), Err(Chip8Error::InvalidKey { key: 0x10 }));
+
+        emulator.memory[0x200..0x202].clone_from_slice(&[0xF0, 0x33]);
+        emulator.index_register = 0xFFE;
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::AddressOutOfRange { address: 0x1000 })
+        );
+
+        emulator.program_counter = 0xFFF;
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::AddressOutOfRange { address: 0x1000 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rom_too_large() {
+        let path = std::env::temp_dir().join("chip8_test_rom_too_large.ch8");
+        std::fs::write(&path, vec![0; 3585]).unwrap();
+        let result = Emulator::new(path.to_str(), EmulatorConfig::new(Quirks::SCHIP_MODERN)).await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            result.err(),
+            Some(Chip8Error::RomTooLarge {
+                size: 3585,
+                max_size: 3584
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_frame() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
+        // loop forever adding 1 to register 0
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x70, 0x01, 0x12, 0x00]);
+        emulator.delay_timer.counter = 10;
+
+        let outcome = emulator.run_frame().unwrap();
+        assert_eq!(outcome.instructions_executed, 12);
+        assert!(!outcome.screen_changed);
+        assert!(!outcome.waiting_for_key);
+        assert_eq!(emulator.registers[0], 6);
+        assert_eq!(emulator.delay_timer.counter, 9);
+
+        emulator.memory[0x200..0x202].clone_from_slice(&[0xF0, 0x0A]);
+        let outcome = emulator.run_frame().unwrap();
+        assert_eq!(outcome.instructions_executed, 1);
+        assert!(outcome.waiting_for_key);
+    }
+
+    #[tokio::test]
+    async fn test_buzzer_follows_sound_timer() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
+        let capture = CaptureSink::new();
+        emulator.set_audio_sink(Box::new(capture.clone()));
+        // set the sound timer to 3, then loop forever
+        emulator.memory[0x200..0x206].clone_from_slice(&[0x60, 0x03, 0xF0, 0x18, 0x12, 0x04]);
+
+        for _ in 0..5 {
+            emulator.run_frame().unwrap();
+        }
+        assert_eq!(
+            capture.transitions(),
+            vec![
+                BuzzerTransition { frame: 0, on: true },
+                BuzzerTransition {
+                    frame: 3,
+                    on: false
+                },
+            ]
+        );
+    }
+
+    /// Builds an emulator that understands the SUPER-CHIP instructions.
+    async fn super_chip_emulator(quirks: Quirks) -> Emulator {
+        let mut config = EmulatorConfig::new(quirks);
+        config.instruction_set = InstructionSet::SuperChip;
+        Emulator::new(None, config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resolution_switch() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x00, 0xFF, 0x00, 0xFE]);
+
+        assert!(emulator.step().unwrap().screen_changed);
+        assert_eq!(emulator.framebuffer.width(), 128);
+        assert_eq!(emulator.framebuffer.height(), 64);
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.width(), 64);
+        assert_eq!(emulator.framebuffer.height(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_super_chip_opcodes_are_sys_on_chip8() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x00, 0xFF]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.width(), 64);
+        assert_eq!(emulator.program_counter, 0x202);
+    }
+
+    #[tokio::test]
+    async fn test_draw_large_sprite() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.set_high_resolution(true);
+        emulator.index_register = 0x300;
+        emulator.memory[0x300..0x320].clone_from_slice(&[[0b10000000, 0b00000001]; 16].concat());
+        emulator.registers[0] = 100;
+        emulator.registers[1] = 10;
+        emulator.draw_to_screen(0, 1, 0).unwrap();
+        assert!(emulator.framebuffer.get(100, 10));
+        assert!(emulator.framebuffer.get(115, 25));
+        assert!(!emulator.framebuffer.get(101, 10));
+        assert_eq!(emulator.registers[0xF], 0);
+    }
+
+    #[tokio::test]
+    async fn test_collision_row_count() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.set_high_resolution(true);
+        emulator.index_register = 0x300;
+        emulator.memory[0x300..0x304].clone_from_slice(&[0xFF; 4]);
+        emulator.registers[0] = 0;
+        emulator.registers[1] = 0;
+        emulator.draw_to_screen(0, 1, 4).unwrap();
+        assert_eq!(emulator.registers[0xF], 0);
+
+        // 3 of the 4 rows overlap the sprite already drawn
+        emulator.registers[1] = 1;
+        emulator.draw_to_screen(0, 1, 4).unwrap();
+        assert_eq!(emulator.registers[0xF], 3);
+
+        // 2 rows are clipped at the bottom of the screen
+        emulator.registers[1] = 62;
+        emulator.draw_to_screen(0, 1, 4).unwrap();
+        assert_eq!(emulator.registers[0xF], 2);
+
+        // low resolution only reports whether there was a collision
+        emulator.set_high_resolution(false);
+        emulator.registers[1] = 0;
+        emulator.draw_to_screen(0, 1, 4).unwrap();
+        emulator.registers[1] = 1;
+        emulator.draw_to_screen(0, 1, 4).unwrap();
+        assert_eq!(emulator.registers[0xF], 1);
+    }
+
+    #[tokio::test]
+    async fn test_scroll() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_MODERN).await;
+        emulator.framebuffer.toggle(10, 10);
+        // scroll down 3, right 4, left 4
+        emulator.memory[0x200..0x206].clone_from_slice(&[0x00, 0xC3, 0x00, 0xFB, 0x00, 0xFC]);
+        emulator.step().unwrap();
+        assert!(emulator.framebuffer.get(10, 13));
+        emulator.step().unwrap();
+        assert!(emulator.framebuffer.get(14, 13));
+        emulator.step().unwrap();
+        assert!(emulator.framebuffer.get(10, 13));
+        assert_eq!(
+            emulator
+                .framebuffer
+                .pixels()
+                .iter()
+                .filter(|pixel| **pixel != 0)
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exit() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_MODERN).await;
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x00, 0xFD]);
+        let outcome = emulator.run_frame().unwrap();
+        assert!(outcome.exited);
+        assert_eq!(outcome.instructions_executed, 1);
+        assert!(emulator.has_exited());
+        assert_eq!(emulator.program_counter, 0x200);
+    }
+
+    #[tokio::test]
+    async fn test_set_index_register_to_big_font_character() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.registers[0] = 0x18;
+        emulator.memory[0x200..0x202].clone_from_slice(&[0xF0, 0x30]);
+        emulator.step().unwrap();
+        let casted_index_register = emulator.index_register as usize;
+        assert_eq!(
+            &emulator.memory[casted_index_register..casted_index_register + 10],
+            &[0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flags_persist() {
+        let store = MemoryFlagStore::new();
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.set_flag_store(Box::new(store.clone()));
+        emulator.registers[..3].clone_from_slice(&[10, 20, 30]);
+        // save V0 to V2, then load V0 to V1
+        emulator.memory[0x200..0x206].clone_from_slice(&[0xF2, 0x75, 0x60, 0x00, 0xF1, 0x85]);
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        assert_eq!(emulator.registers[0], 0);
+        emulator.step().unwrap();
+        assert_eq!(emulator.registers[..3], [10, 20, 30]);
+
+        // a restarted emulator running the same ROM sees the saved flags
+        let mut restarted = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        restarted.set_flag_store(Box::new(store));
+        restarted.memory[0x200..0x202].clone_from_slice(&[0xF2, 0x85]);
+        restarted.step().unwrap();
+        assert_eq!(restarted.registers[..3], [10, 20, 30]);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_flags() {
+        // a file where the flags directory should be makes every save fail
+        let path = std::env::temp_dir().join("chip8_test_unwritable_flags");
+        std::fs::write(&path, []).unwrap();
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.set_flag_store(Box::new(crate::flags::FileFlagStore::new(&path)));
+        emulator.registers[..2].clone_from_slice(&[10, 20]);
+        // save V0 to V1, then load them back
+        emulator.memory[0x200..0x206].clone_from_slice(&[0xF1, 0x75, 0x60, 0x00, 0xF1, 0x85]);
+        let saved = emulator.step();
+        std::fs::remove_file(&path).unwrap();
+        assert!(saved.is_ok());
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        assert_eq!(emulator.registers[..2], [10, 20]);
+    }
+
+    #[tokio::test]
+    async fn test_flag_out_of_range() {
+        let mut emulator = super_chip_emulator(Quirks::SCHIP_1_1).await;
+        emulator.memory[0x200..0x202].clone_from_slice(&[0xF8, 0x75]);
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::FlagOutOfRange {
+                register: 8,
+                flag_count: 8
+            })
+        );
+    }
+
+    /// Builds an emulator that understands the XO-CHIP instructions.
+    async fn xo_chip_emulator() -> Emulator {
+        let mut config = EmulatorConfig::new(Quirks::XO_CHIP);
+        config.instruction_set = InstructionSet::XoChip;
+        Emulator::new(None, config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_xo_chip_memory() {
+        let mut emulator = xo_chip_emulator().await;
+        assert_eq!(emulator.memory.len(), 65536);
+        // I := 0xFFF0, then save V0 there
+        emulator.memory[0x200..0x206].clone_from_slice(&[0xF0, 0x00, 0xFF, 0xF0, 0xF0, 0x55]);
+        emulator.registers[0] = 42;
+        emulator.step().unwrap();
+        assert_eq!(emulator.index_register, 0xFFF0);
+        assert_eq!(emulator.program_counter, 0x204);
+        emulator.step().unwrap();
+        assert_eq!(emulator.memory[0xFFF0], 42);
+    }
+
+    #[tokio::test]
+    async fn test_skip_over_long_instruction() {
+        let mut emulator = xo_chip_emulator().await;
+        // skip if V0 == 0 over F000 NNNN
+        emulator.memory[0x200..0x206].clone_from_slice(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.program_counter, 0x206);
+    }
+
+    #[tokio::test]
+    async fn test_register_ranges() {
+        let mut emulator = xo_chip_emulator().await;
+        emulator.index_register = 0x300;
+        emulator.registers[2..5].clone_from_slice(&[1, 2, 3]);
+        // save V2 - V4, then load them back reversed into V7 - V5
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x52, 0x42, 0x57, 0x53]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.memory[0x300..0x303], [1, 2, 3]);
+        assert_eq!(emulator.index_register, 0x300);
+        emulator.step().unwrap();
+        assert_eq!(emulator.registers[5..8], [3, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_scroll_up() {
+        let mut emulator = xo_chip_emulator().await;
+        emulator.framebuffer.toggle(10, 10);
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x00, 0xD3]);
+        assert!(emulator.step().unwrap().screen_changed);
+        assert!(emulator.framebuffer.get(10, 7));
+    }
+
+    #[tokio::test]
+    async fn test_draw_to_planes() {
+        let mut emulator = xo_chip_emulator().await;
+        emulator.index_register = 0x300;
+        // one row for the first plane, one for the second
+        emulator.memory[0x300..0x302].clone_from_slice(&[0b11000000, 0b01100000]);
+        // select both planes, then draw a 1-row sprite at (0, 0)
+        emulator.memory[0x200..0x204].clone_from_slice(&[0xF3, 0x01, 0xD0, 0x01]);
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.planes(0, 0), 1);
+        assert_eq!(emulator.framebuffer.planes(1, 0), 3);
+        assert_eq!(emulator.framebuffer.planes(2, 0), 2);
+        assert_eq!(emulator.registers[0xF], 0);
+
+        // clearing and scrolling only affect the selected plane
+        emulator.memory[0x204..0x20A].clone_from_slice(&[0xF2, 0x01, 0x00, 0xFB, 0x00, 0xE0]);
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.planes(1, 0), 1);
+        assert_eq!(emulator.framebuffer.planes(5, 0), 2);
+        assert_eq!(emulator.framebuffer.planes(6, 0), 2);
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.planes(0, 0), 1);
+        assert_eq!(emulator.framebuffer.planes(1, 0), 1);
+        assert_eq!(emulator.framebuffer.planes(5, 0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_audio_pattern() {
+        let mut emulator = xo_chip_emulator().await;
+        let capture = CaptureSink::new();
+        emulator.set_audio_sink(Box::new(capture.clone()));
+        emulator.index_register = 0x300;
+        emulator.memory[0x300..0x310].clone_from_slice(&[0xF0; 16]);
+        emulator.registers[1] = 112;
+        // pitch := V1, then load the pattern
+        emulator.memory[0x200..0x204].clone_from_slice(&[0xF1, 0x3A, 0xF0, 0x02]);
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+
+        let pattern = AudioPattern {
+            pattern: [0xF0; 16],
+            pitch: 112,
+        };
+        assert_eq!(
+            capture.patterns(),
+            vec![
+                PatternChange {
+                    frame: 0,
+                    pattern: None
+                },
+                PatternChange {
+                    frame: 0,
+                    pattern: None
+                },
+                PatternChange {
+                    frame: 0,
+                    pattern: Some(pattern)
+                },
+            ]
+        );
+        assert_eq!(pattern.playback_rate(), 8000.0);
+    }
+
+    async fn mega_chip_emulator() -> Emulator {
+        let mut config = EmulatorConfig::new(Quirks::SCHIP_MODERN);
+        config.instruction_set = InstructionSet::MegaChip;
+        Emulator::new(None, config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mega_mode_switch() {
+        let mut emulator = mega_chip_emulator().await;
+        assert_eq!(emulator.memory.len(), 1 << 24);
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x00, 0x11, 0x00, 0x10]);
+        assert!(emulator.step().unwrap().screen_changed);
+        assert_eq!(emulator.framebuffer.width(), 256);
+        assert_eq!(emulator.framebuffer.height(), 192);
+        assert_eq!(emulator.framebuffer.color(0, 0), Some([0, 0, 0]));
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.width(), 64);
+        assert_eq!(emulator.framebuffer.color(0, 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_mega_opcodes_are_sys_elsewhere() {
+        let mut emulator = xo_chip_emulator().await;
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x00, 0x11]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.width(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_huge_index_register() {
+        let mut emulator = mega_chip_emulator().await;
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x01, 0x12, 0x34, 0x56]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.index_register, 0x123456);
+        assert_eq!(emulator.program_counter, 0x204);
+
+        // skips step over both words
+        emulator.memory[0x204..0x20A].clone_from_slice(&[0x30, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.program_counter, 0x20A);
+
+        emulator.index_register = 0xFFFFFF;
+        emulator.registers[0] = 2;
+        emulator.add_to_index(0);
+        assert_eq!(emulator.index_register, 1);
+        assert_eq!(emulator.registers[0xF], 1);
+    }
+
+    #[tokio::test]
+    async fn test_draw_color_sprite() {
+        let mut emulator = mega_chip_emulator().await;
+        emulator.set_mega_mode(true);
+        // two palette entries, then a 2x2 sprite with a transparent corner
+        emulator.index_register = 0x300;
+        emulator.memory[0x300..0x308]
+            .clone_from_slice(&[0xFF, 0xFF, 0x00, 0x00, 0xFF, 0x00, 0x00, 0xFF]);
+        emulator.memory[0x400..0x404].clone_from_slice(&[1, 2, 0, 1]);
+        emulator.registers[0] = 254;
+        emulator.registers[1] = 10;
+        // ldpal 2, sprw 2, sprh 2, i := 0x400, sprite v0 v1 0
+        emulator.memory[0x200..0x20A]
+            .clone_from_slice(&[0x02, 0x02, 0x03, 0x02, 0x04, 0x02, 0xA4, 0x00, 0xD0, 0x10]);
+        for _ in 0..5 {
+            emulator.step().unwrap();
+        }
+        assert_eq!(emulator.framebuffer.planes(254, 10), 1);
+        assert_eq!(emulator.framebuffer.color(254, 10), Some([0xFF, 0, 0]));
+        assert_eq!(emulator.framebuffer.color(255, 10), Some([0, 0, 0xFF]));
+        assert_eq!(emulator.framebuffer.planes(254, 11), 0);
+        assert_eq!(emulator.framebuffer.planes(255, 11), 1);
+        assert_eq!(emulator.registers[0xF], 0);
+
+        // drawing over color 1 collides once it is the collision color, and blends at 50%
+        emulator.memory[0x20A..0x20E].clone_from_slice(&[0x09, 0x01, 0x08, 0x02]);
+        emulator.memory[0x20E..0x210].clone_from_slice(&[0xD0, 0x10]);
+        emulator.memory[0x400] = 2;
+        for _ in 0..3 {
+            emulator.step().unwrap();
+        }
+        assert_eq!(emulator.registers[0xF], 1);
+        assert_eq!(emulator.framebuffer.planes(254, 10), 2);
+        assert_eq!(emulator.framebuffer.color(254, 10), Some([0x7F, 0, 0x7F]));
+        // the sprite is clipped rather than wrapped
+        assert_eq!(emulator.framebuffer.planes(0, 10), 0);
+    }
+
+    #[tokio::test]
+    async fn test_draw_font_in_mega_mode() {
+        let mut emulator = mega_chip_emulator().await;
+        emulator.set_mega_mode(true);
+        emulator.set_index_register_to_font_character(0);
+        emulator.draw_to_screen(0, 0, 5).unwrap();
+        // the top row of 0 is 0xF0
+        assert_eq!(emulator.framebuffer.planes(3, 0), 0xFF);
+        assert_eq!(emulator.framebuffer.color(3, 0), Some([0xFF, 0xFF, 0xFF]));
+        assert_eq!(emulator.framebuffer.planes(4, 0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_mega_scroll_and_clear() {
+        let mut emulator = mega_chip_emulator().await;
+        emulator.set_mega_mode(true);
+        emulator.framebuffer.set_color(5, 10, 7, [1, 2, 3]);
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x00, 0xB4, 0x00, 0xE0]);
+        assert!(emulator.step().unwrap().screen_changed);
+        assert_eq!(emulator.framebuffer.planes(5, 6), 7);
+        assert_eq!(emulator.framebuffer.color(5, 6), Some([1, 2, 3]));
+        emulator.step().unwrap();
+        assert_eq!(emulator.framebuffer.planes(5, 6), 0);
+        assert_eq!(emulator.framebuffer.color(5, 6), Some([0, 0, 0]));
+    }
+
+    #[tokio::test]
+    async fn test_sample_playback() {
+        let mut emulator = mega_chip_emulator().await;
+        let capture = CaptureSink::new();
+        emulator.set_audio_sink(Box::new(capture.clone()));
+        emulator.index_register = 0x300;
+        emulator.memory[0x300..0x309]
+            .clone_from_slice(&[0x1F, 0x40, 0x00, 0x00, 0x03, 0x00, 0x80, 0xFF, 0x00]);
+        // play once, then stop
+        emulator.memory[0x200..0x204].clone_from_slice(&[0x06, 0x01, 0x07, 0x00]);
+        emulator.step().unwrap();
+        emulator.step().unwrap();
+        assert_eq!(
+            capture.samples(),
+            vec![
+                SampleChange {
+                    frame: 0,
+                    sample: Some(Sample {
+                        sample_rate: 8000,
+                        data: vec![0x80, 0xFF, 0x00],
+                        looping: false
+                    })
+                },
+                SampleChange {
+                    frame: 0,
+                    sample: None
+                },
+            ]
+        );
+
+        // a sound running past the end of memory is an error
+        emulator.index_register = 0xFFFFFE;
+        emulator.memory[0x204..0x206].clone_from_slice(&[0x06, 0x00]);
+        assert_eq!(
+            emulator.step(),
+            Err(Chip8Error::AddressOutOfRange { address: 0x1000000 })
+        );
+    }
+
+    async fn vip_variant_emulator(instruction_set: InstructionSet) -> Emulator {
+        let mut config = EmulatorConfig::new(Quirks::COSMAC_VIP);
+        config.instruction_set = instruction_set;
+        Emulator::new(None, config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_two_page_hires() {
+        let mut emulator = vip_variant_emulator(InstructionSet::TwoPageHires).await;
+        assert_eq!(emulator.framebuffer.width(), 64);
+        assert_eq!(emulator.framebuffer.height(), 64);
+
+        // 1260 at the start enters the program at 0x2C0
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x12, 0x60]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.program_counter, 0x2C0);
+
+        // draw in the lower page, then clear it with 0230
+        emulator.index_register = 0x300;
+        emulator.memory[0x300] = 0x80;
+        emulator.registers[1] = 50;
+        emulator.memory[0x2C0..0x2C4].clone_from_slice(&[0xD0, 0x11, 0x02, 0x30]);
+        emulator.step().unwrap();
+        assert!(emulator.framebuffer.get(0, 50));
+        assert!(emulator.step().unwrap().screen_changed);
+        assert!(!emulator.framebuffer.get(0, 50));
+
+        // elsewhere 1260 is an ordinary jump
+        emulator.memory[0x2C4..0x2C6].clone_from_slice(&[0x12, 0x60]);
+        emulator.step().unwrap();
+        assert_eq!(emulator.program_counter, 0x260);
+    }
+
+    #[tokio::test]
+    async fn test_chip8x_colors() {
+        let mut emulator = vip_variant_emulator(InstructionSet::Chip8X).await;
+        let foreground = crate::chip8x::FOREGROUND_COLORS;
+        let background = crate::chip8x::BACKGROUND_COLORS;
+
+        // 02A0 moves on to the next background color
+        emulator.memory[0x200..0x202].clone_from_slice(&[0x02, 0xA0]);
+        emulator.step().unwrap();
+        let color = |emulator: &Emulator, x, y, lit| {
+            emulator.framebuffer.color_zones().unwrap().color(x, y, lit)
+        };
+        assert_eq!(color(&emulator, 0, 0, false), background[1]);
+
+        // BXY0: columns 2 to 3 and zone rows 1 to 2 turn yellow
+        emulator.registers[0] = 0x12;
+        emulator.registers[1] = 0x11;
+        emulator.registers[2] = 5;
+        emulator.memory[0x202..0x204].clone_from_slice(&[0xB0, 0x20]);
+        emulator.step().unwrap();
+        assert_eq!(color(&emulator, 16, 4, true), foreground[5]);
+        assert_eq!(color(&emulator, 31, 11, true), foreground[5]);
+        assert_eq!(color(&emulator, 32, 11, true), foreground[1]);
+        assert_eq!(color(&emulator, 16, 12, true), foreground[1]);
+
+        // BXYN: 2 rows of the column at (V3, V5) take the color in V4, the register after V3
+        emulator.registers[3] = 60;
+        emulator.registers[4] = 4;
+        emulator.registers[5] = 20;
+        emulator.memory[0x204..0x206].clone_from_slice(&[0xB3, 0x52]);
+        emulator.step().unwrap();
+        assert_eq!(color(&emulator, 56, 20, true), foreground[4]);
+        assert_eq!(color(&emulator, 63, 21, true), foreground[4]);
+        assert_eq!(color(&emulator, 63, 22, true), foreground[1]);
+
+        // BNNN is not a jump on CHIP-8X
+        assert_eq!(emulator.program_counter, 0x206);
+    }
+
+    #[tokio::test]
+    async fn test_chip8x_second_keypad() {
+        let mut emulator = vip_variant_emulator(InstructionSet::Chip8X).await;
+        emulator.registers[0] = 0x1;
+        // skip if key 1 is pressed on the second keypad, twice
+        emulator.memory[0x200..0x204].clone_from_slice(&[0xE0, 0xF2, 0xE0, 0xF2]);
+        emulator.handle_input(&Pressed, &2);
+        emulator.step().unwrap();
+        assert_eq!(emulator.program_counter, 0x202);
+        emulator.handle_input(&Pressed, &8);
+        emulator.step().unwrap();
+        assert_eq!(emulator.program_counter, 0x206);
+
+        // the second keypad does not answer FX0A
+        emulator.program_counter = 0x200;
+        emulator.memory[0x200..0x202].clone_from_slice(&[0xF5, 0x0A]);
+        emulator.step().unwrap();
+        emulator.handle_input(&Released, &9);
+        emulator.handle_input(&Pressed, &9);
+        assert!(emulator.is_waiting_for_key());
+    }
+
+    #[tokio::test]
+    async fn test_vip_variants_draw_nothing_for_dxy0() {
+        for instruction_set in [InstructionSet::TwoPageHires, InstructionSet::Chip8X] {
+            let mut emulator = vip_variant_emulator(instruction_set).await;
+            emulator.index_register = 0x300;
+            emulator.memory[0x300..0x320].fill(0xFF);
+            emulator.memory[0x200..0x202].clone_from_slice(&[0xD0, 0x10]);
+            assert!(emulator.memory_access().reads.is_empty());
+            emulator.step().unwrap();
+            assert!(!emulator.framebuffer.get(0, 0));
+            assert_eq!(emulator.registers[0xF], 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poke_and_set_key() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.poke(0x200, &[0xF3, 0x0A]).unwrap();
+        assert_eq!(emulator.memory()[0x200..0x202], [0xF3, 0x0A]);
+        assert_eq!(
+            emulator.poke(0xFFF, &[1, 2]),
+            Err(Chip8Error::AddressOutOfRange { address: 0x1000 })
+        );
+
+        // the VIP finishes FX0A when the key is released
+        emulator.step().unwrap();
+        emulator.set_key(0xB, Pressed).unwrap();
+        emulator.set_key(0xB, Released).unwrap();
+        assert_eq!(emulator.registers()[3], 0xB);
+        assert_eq!(
+            emulator.set_key(0x10, Pressed),
+            Err(Chip8Error::InvalidKey { key: 0x10 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_access() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.index_register = 0x300;
+        emulator.memory[0x200..0x202].copy_from_slice(&[0xF3, 0x55]);
+        assert_eq!(emulator.memory_access().writes, 0x300..0x304);
+        assert!(emulator.memory_access().reads.is_empty());
+        emulator.memory[0x200..0x202].copy_from_slice(&[0xD0, 0x15]);
+        assert_eq!(emulator.memory_access().reads, 0x300..0x305);
+        emulator.memory[0x200..0x202].copy_from_slice(&[0x60, 0x15]);
+        assert_eq!(emulator.memory_access(), Default::default());
+
+        // XO-CHIP draws a sprite for each selected plane
+        let mut emulator = xo_chip_emulator().await;
+        emulator.index_register = 0x300;
+        emulator.selected_planes = 0b11;
+        emulator.memory[0x200..0x202].copy_from_slice(&[0xD0, 0x10]);
+        assert_eq!(emulator.memory_access().reads, 0x300..0x340);
+    }
+
+    #[tokio::test]
+    async fn test_platform_start_address() {
+        let path = std::env::temp_dir().join("chip8_test_platform_start_address.ch8");
+        std::fs::write(&path, [0x60, 0x2A]).unwrap();
+        let config = EmulatorConfig::for_platform(&Platform::ETI_660);
+        let mut emulator = Emulator::new(path.to_str(), config).await.unwrap();
+        assert_eq!(emulator.program_counter, 0x600);
+        assert_eq!(emulator.memory[0x200], 0);
+        emulator.step().unwrap();
+        assert_eq!(emulator.registers[0], 0x2A);
+
+        // the ROM may fill memory from the start address to the end
+        std::fs::write(&path, vec![0; 2561]).unwrap();
+        let result = Emulator::new(path.to_str(), config).await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            result.err(),
+            Some(Chip8Error::RomTooLarge {
+                size: 2561,
+                max_size: 2560
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_platform_font() {
+        let mut emulator = Emulator::new(None, EmulatorConfig::for_platform(&Platform::COSMAC_VIP))
+            .await
+            .unwrap();
+        emulator.registers[0] = 0x1;
+        emulator.set_index_register_to_font_character(0);
+        let start = emulator.index_register as usize;
+        assert_eq!(
+            &emulator.memory[start..start + 5],
+            &[0x60, 0x20, 0x20, 0x20, 0x70]
+        );
+        // the VIP has no big font
+        assert!(emulator.memory[start + 75..start + 240]
+            .iter()
+            .all(|byte| *byte == 0));
+    }
+
+    #[tokio::test]
+    async fn test_random_initial_memory() {
+        let mut config = EmulatorConfig::for_platform(&Platform::COSMAC_VIP);
+        config.memory.initial_memory = InitialMemory::Random;
+        config.rng_seed = Some(7);
+        let emulator = Emulator::new(None, config).await.unwrap();
+        let again = Emulator::new(None, config).await.unwrap();
+        assert_eq!(emulator.memory, again.memory);
+        assert!(emulator.memory[0x200..].iter().any(|byte| *byte != 0));
+        // the font is loaded over the random bytes
+        assert_eq!(emulator.memory[0x50..0x55], [0xF0, 0x90, 0x90, 0x90, 0xF0]);
     }
 
     #[tokio::test]
     async fn test_set_index_register_to_font_character() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 0xAB;
         emulator.set_index_register_to_font_character(0);
-        let casted_index_register = emulator.index_register as usize;
+        let mut casted_index_register = emulator.index_register as usize;
         assert_eq!(
             &emulator.memory[casted_index_register..casted_index_register + 5],
             &[0xE0, 0x90, 0xE0, 0x90, 0xE0]
@@ -1063,6 +2914,7 @@ mod emulator_tests {
 
         emulator.registers[0] = 0xA9;
         emulator.set_index_register_to_font_character(0);
+        casted_index_register = emulator.index_register as usize;
         assert_eq!(
             &emulator.memory[casted_index_register..casted_index_register + 5],
             &[0xF0, 0x90, 0xF0, 0x10, 0xF0]
@@ -1075,10 +2927,12 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_binary_coded_decimal_conversion() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.index_register = 0x300;
         emulator.registers[0] = 156;
-        emulator.binary_coded_decimal_conversion(0);
+        emulator.binary_coded_decimal_conversion(0).unwrap();
 
         let casted_index_register = emulator.index_register as usize;
         assert_eq!(
@@ -1089,41 +2943,56 @@ mod emulator_tests {
 
     #[tokio::test]
     async fn test_store_registers_to_memory() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.registers[0] = 4;
         emulator.registers[1] = 2;
         emulator.index_register = 0x200;
-        emulator.store_registers_to_memory(1);
+        emulator.store_registers_to_memory(1).unwrap();
         assert_eq!(emulator.memory[0x200], 4);
         assert_eq!(emulator.memory[0x201], 2);
         assert_eq!(emulator.index_register, 0x202);
 
-        emulator = Emulator::new(None, true, false).await;
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
         emulator.registers[0] = 4;
         emulator.registers[1] = 2;
         emulator.index_register = 0x200;
-        emulator.store_registers_to_memory(1);
+        emulator.store_registers_to_memory(1).unwrap();
         assert_eq!(emulator.memory[0x200], 4);
         assert_eq!(emulator.memory[0x201], 2);
         assert_eq!(emulator.index_register, 0x200);
+
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::CHIP_48))
+            .await
+            .unwrap();
+        emulator.index_register = 0x200;
+        emulator.store_registers_to_memory(1).unwrap();
+        assert_eq!(emulator.index_register, 0x201);
     }
 
     #[tokio::test]
     async fn test_store_memory_to_registers() {
-        let mut emulator = Emulator::new(None, true, false).await;
+        let mut emulator = Emulator::new(None, EmulatorConfig::new(Quirks::COSMAC_VIP))
+            .await
+            .unwrap();
         emulator.memory[0x200] = 4;
         emulator.memory[0x201] = 2;
         emulator.index_register = 0x200;
-        emulator.store_memory_to_registers(1);
+        emulator.store_memory_to_registers(1).unwrap();
         assert_eq!(emulator.registers[0], 4);
         assert_eq!(emulator.registers[1], 2);
         assert_eq!(emulator.index_register, 0x202);
 
-        emulator = Emulator::new(None, true, false).await;
+        emulator = Emulator::new(None, EmulatorConfig::new(Quirks::SCHIP_MODERN))
+            .await
+            .unwrap();
         emulator.memory[0x200] = 4;
         emulator.memory[0x201] = 2;
         emulator.index_register = 0x200;
-        emulator.store_memory_to_registers(1);
+        emulator.store_memory_to_registers(1).unwrap();
         assert_eq!(emulator.registers[0], 4);
         assert_eq!(emulator.registers[1], 2);
         assert_eq!(emulator.index_register, 0x200);
@@ -0,0 +1,81 @@
+/// Everything that can go wrong while loading or running a ROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// A return (00EE) was executed with nothing on the stack.
+    StackUnderflow { address: usize },
+    /// A call (2NNN) was executed with the stack already full.
+    StackOverflow { address: usize },
+    /// The ROM does not fit in memory after the start address.
+    RomTooLarge { size: usize, max_size: usize },
+    /// The ROM file could not be read.
+    RomUnreadable { path: String, reason: String },
+    /// An instruction tried to access memory past the end of the address space.
+    AddressOutOfRange { address: usize },
+    /// A key instruction referenced a key that is not on the 16-key keypad.
+    InvalidKey { key: u8 },
+    /// The opcode does not correspond to any instruction.
+    UnknownOpcode { opcode: u16, address: usize },
+    /// The RPL user flags could not be saved.
+    FlagsUnwritable { path: String, reason: String },
+    /// FX75 or FX85 referenced more registers than there are RPL user flags.
+    FlagOutOfRange { register: u8, flag_count: usize },
+    /// No audio output device could be opened.
+    AudioUnavailable { reason: String },
+    /// A command-line argument was missing or could not be understood.
+    InvalidArgument { argument: String, reason: String },
+}
+
+impl std::fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Chip8Error::StackUnderflow { address } => {
+                write!(
+                    f,
+                    "stack underflow: return with an empty stack at {:#05X}",
+                    address
+                )
+            }
+            Chip8Error::StackOverflow { address } => {
+                write!(
+                    f,
+                    "stack overflow: call with a full stack at {:#05X}",
+                    address
+                )
+            }
+            Chip8Error::RomTooLarge { size, max_size } => write!(
+                f,
+                "ROM is {} bytes but at most {} bytes fit in memory",
+                size, max_size
+            ),
+            Chip8Error::RomUnreadable { path, reason } => {
+                write!(f, "could not read ROM {}: {}", path, reason)
+            }
+            Chip8Error::AddressOutOfRange { address } => {
+                write!(f, "address {:#05X} is out of range", address)
+            }
+            Chip8Error::InvalidKey { key } => write!(f, "invalid key {:#04X}", key),
+            Chip8Error::UnknownOpcode { opcode, address } => {
+                write!(f, "unknown opcode {:04X} at {:#05X}", opcode, address)
+            }
+            Chip8Error::FlagsUnwritable { path, reason } => {
+                write!(f, "could not save flags to {}: {}", path, reason)
+            }
+            Chip8Error::FlagOutOfRange {
+                register,
+                flag_count,
+            } => write!(
+                f,
+                "V{:X} is past the last of the {} RPL user flags",
+                register, flag_count
+            ),
+            Chip8Error::AudioUnavailable { reason } => {
+                write!(f, "audio output unavailable: {}", reason)
+            }
+            Chip8Error::InvalidArgument { argument, reason } => {
+                write!(f, "invalid {}: {}", argument, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
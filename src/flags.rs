@@ -0,0 +1,162 @@
+/// SUPER-CHIP had 8 RPL user flags, XO-CHIP has 16.
+pub const MAX_FLAGS: usize = 16;
+
+/// Identifies a ROM by a 64-bit FNV-1a hash of its contents, so renamed copies share their flags.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// Where FX75 saves the RPL user flags so that FX85 can restore them after a restart.
+pub trait FlagStore {
+    /// The flags last saved for the ROM, or all zeroes if none were saved.
+    fn load(&self, rom_hash: u64) -> [u8; MAX_FLAGS];
+    fn save(
+        &mut self,
+        rom_hash: u64,
+        flags: &[u8; MAX_FLAGS],
+    ) -> Result<(), crate::error::Chip8Error>;
+}
+
+/// Keeps one file of flags per ROM in a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFlagStore {
+    directory: std::path::PathBuf,
+}
+
+impl FileFlagStore {
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// `$XDG_DATA_HOME/chip8/flags`, falling back to `~/.local/share/chip8/flags`,
+    /// or `./flags` if there is no home directory.
+    pub fn default_directory() -> std::path::PathBuf {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| std::path::PathBuf::from(home).join(".local").join("share"))
+            });
+        match data_home {
+            Some(data_home) => data_home.join("chip8").join("flags"),
+            None => std::path::PathBuf::from("flags"),
+        }
+    }
+
+    /// The file holding the flags for the ROM.
+    pub fn path(&self, rom_hash: u64) -> std::path::PathBuf {
+        self.directory.join(format!("{:016x}.flags", rom_hash))
+    }
+}
+
+impl Default for FileFlagStore {
+    fn default() -> Self {
+        Self::new(Self::default_directory())
+    }
+}
+
+impl FlagStore for FileFlagStore {
+    fn load(&self, rom_hash: u64) -> [u8; MAX_FLAGS] {
+        let mut flags = [0; MAX_FLAGS];
+        match std::fs::read(self.path(rom_hash)) {
+            Ok(contents) => {
+                let length = contents.len().min(MAX_FLAGS);
+                flags[..length].clone_from_slice(&contents[..length]);
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => log::warn!(
+                "could not read flags from {}: {}",
+                self.path(rom_hash).display(),
+                error
+            ),
+        }
+        flags
+    }
+
+    fn save(
+        &mut self,
+        rom_hash: u64,
+        flags: &[u8; MAX_FLAGS],
+    ) -> Result<(), crate::error::Chip8Error> {
+        let path = self.path(rom_hash);
+        std::fs::create_dir_all(&self.directory)
+            .and_then(|_| std::fs::write(&path, flags))
+            .map_err(|error| crate::error::Chip8Error::FlagsUnwritable {
+                path: path.display().to_string(),
+                reason: error.to_string(),
+            })
+    }
+}
+
+/// Keeps the flags in memory. Clones share the same flags,
+/// so one can be handed to the emulator while another is kept to inspect them.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFlagStore {
+    flags: std::rc::Rc<std::cell::RefCell<std::collections::HashMap<u64, [u8; MAX_FLAGS]>>>,
+}
+
+impl MemoryFlagStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl FlagStore for MemoryFlagStore {
+    fn load(&self, rom_hash: u64) -> [u8; MAX_FLAGS] {
+        self.flags
+            .borrow()
+            .get(&rom_hash)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn save(
+        &mut self,
+        rom_hash: u64,
+        flags: &[u8; MAX_FLAGS],
+    ) -> Result<(), crate::error::Chip8Error> {
+        self.flags.borrow_mut().insert(rom_hash, *flags);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod flags_tests {
+    use super::{rom_hash, FileFlagStore, FlagStore, MemoryFlagStore};
+
+    #[test]
+    fn test_rom_hash() {
+        assert_eq!(rom_hash(&[]), 0xCBF2_9CE4_8422_2325);
+        assert_eq!(rom_hash(b"a"), 0xAF63_DC4C_8601_EC8C);
+        assert_ne!(rom_hash(&[0x12, 0x00]), rom_hash(&[0x00, 0x12]));
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryFlagStore::new();
+        let mut handle = store.clone();
+        assert_eq!(store.load(1), [0; 16]);
+        handle.save(1, &[7; 16]).unwrap();
+        assert_eq!(store.load(1), [7; 16]);
+        assert_eq!(store.load(2), [0; 16]);
+    }
+
+    #[test]
+    fn test_file_store() {
+        let directory = std::env::temp_dir().join("chip8_test_flags");
+        let _ = std::fs::remove_dir_all(&directory);
+        let mut store = FileFlagStore::new(&directory);
+        assert_eq!(store.load(0xABCD), [0; 16]);
+
+        let mut flags = [0; 16];
+        flags[..3].clone_from_slice(&[1, 2, 3]);
+        store.save(0xABCD, &flags).unwrap();
+        assert!(store.path(0xABCD).ends_with("000000000000abcd.flags"));
+        assert_eq!(FileFlagStore::new(&directory).load(0xABCD), flags);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,233 @@
+/// Bit masks selecting the XO-CHIP drawing planes. CHIP-8 and SUPER-CHIP only draw to the first plane.
+pub const FIRST_PLANE: u8 = 0b01;
+pub const SECOND_PLANE: u8 = 0b10;
+pub const ALL_PLANES: u8 = FIRST_PLANE | SECOND_PLANE;
+
+/// The display owned by the emulator. Pixels are indexed from the top-left going bottom-right,
+/// matching how CHIP-8 programs address the screen. Rendering reads from this instead of the CPU writing to the GPU.
+/// Each pixel holds one bit per plane, so with both XO-CHIP planes a pixel is one of 4 palette colors.
+/// In MEGA-CHIP mode each pixel instead holds a palette index from 0 to 255 along with the RGB color that was blended there.
+/// CHIP-8X keeps its color zones alongside the pixels, since clearing the screen leaves them as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    colors: Option<Vec<[u8; 3]>>,
+    color_zones: Option<crate::chip8x::ColorZones>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+            colors: None,
+            color_zones: None,
+        }
+    }
+
+    /// A framebuffer with CHIP-8X color zones.
+    pub fn with_color_zones(width: usize, height: usize) -> Self {
+        Self {
+            color_zones: Some(crate::chip8x::ColorZones::new(width, height)),
+            ..Self::new(width, height)
+        }
+    }
+
+    /// A framebuffer that stores an RGB color for every pixel, as MEGA-CHIP does.
+    pub fn true_color(width: usize, height: usize) -> Self {
+        Self {
+            colors: Some(vec![[0; 3]; width * height]),
+            ..Self::new(width, height)
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The planes lit at each pixel, which are also the pixels' palette indices.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Returns whether the pixel at (x, y) is lit in any plane.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.planes(x, y) != 0
+    }
+
+    /// Returns the planes lit at (x, y), which is the pixel's palette index.
+    pub fn planes(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// The RGB color at (x, y), if this is a true color framebuffer.
+    pub fn color(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        self.colors
+            .as_ref()
+            .map(|colors| colors[y * self.width + x])
+    }
+
+    /// The CHIP-8X color zones, if this framebuffer has them.
+    pub fn color_zones(&self) -> Option<&crate::chip8x::ColorZones> {
+        self.color_zones.as_ref()
+    }
+
+    pub fn color_zones_mut(&mut self) -> Option<&mut crate::chip8x::ColorZones> {
+        self.color_zones.as_mut()
+    }
+
+    /// Sets the palette index at (x, y), and its color if this is a true color framebuffer.
+    pub fn set_color(&mut self, x: usize, y: usize, index: u8, rgb: [u8; 3]) {
+        self.pixels[y * self.width + x] = index;
+        if let Some(colors) = &mut self.colors {
+            colors[y * self.width + x] = rgb;
+        }
+    }
+
+    /// XORs the pixel at (x, y) in the first plane. Returns true if the pixel was turned off, i.e. a collision.
+    pub fn toggle(&mut self, x: usize, y: usize) -> bool {
+        self.toggle_plane(x, y, FIRST_PLANE)
+    }
+
+    /// XORs the pixel at (x, y) in a single plane. Returns true if the pixel was turned off in that plane.
+    pub fn toggle_plane(&mut self, x: usize, y: usize, plane: u8) -> bool {
+        let pixel = &mut self.pixels[y * self.width + x];
+        *pixel ^= plane;
+        *pixel & plane == 0
+    }
+
+    /// Turns every pixel off.
+    pub fn clear(&mut self) {
+        self.clear_planes(u8::MAX);
+    }
+
+    /// Turns every pixel off in the selected planes. Colors are turned black.
+    pub fn clear_planes(&mut self, planes: u8) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= !planes);
+        if let Some(colors) = &mut self.colors {
+            colors.fill([0; 3]);
+        }
+    }
+
+    /// Changes the resolution, turning every pixel off and dropping the colors and color zones.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = Self::new(width, height);
+    }
+
+    /// Moves the image right by `dx` and down by `dy` pixels. Negative amounts move it left or up.
+    /// Pixels moved off the edge are lost and the pixels uncovered are turned off.
+    pub fn scroll(&mut self, dx: isize, dy: isize) {
+        self.scroll_planes(dx, dy, u8::MAX);
+    }
+
+    /// Scrolls only the selected planes, leaving the others in place. Colors always scroll.
+    pub fn scroll_planes(&mut self, dx: isize, dy: isize, planes: u8) {
+        let mut scrolled: Vec<u8> = self.pixels.iter().map(|pixel| pixel & !planes).collect();
+        let mut scrolled_colors = self
+            .colors
+            .as_ref()
+            .map(|colors| vec![[0; 3]; colors.len()]);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let from_x = x as isize - dx;
+                let from_y = y as isize - dy;
+                if (0..self.width as isize).contains(&from_x)
+                    && (0..self.height as isize).contains(&from_y)
+                {
+                    scrolled[y * self.width + x] |=
+                        self.planes(from_x as usize, from_y as usize) & planes;
+                    if let Some(scrolled_colors) = &mut scrolled_colors {
+                        scrolled_colors[y * self.width + x] = self
+                            .color(from_x as usize, from_y as usize)
+                            .unwrap_or_default();
+                    }
+                }
+            }
+        }
+        self.pixels = scrolled;
+        self.colors = scrolled_colors;
+    }
+}
+
+#[cfg(test)]
+mod framebuffer_tests {
+    use super::{Framebuffer, FIRST_PLANE, SECOND_PLANE};
+
+    #[test]
+    fn test_toggle() {
+        let mut framebuffer = Framebuffer::new(64, 32);
+        assert!(!framebuffer.toggle(63, 31));
+        assert!(framebuffer.get(63, 31));
+        assert!(framebuffer.toggle(63, 31));
+        assert!(!framebuffer.get(63, 31));
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut framebuffer = Framebuffer::new(128, 64);
+        framebuffer.toggle(0, 0);
+        framebuffer.toggle(127, 63);
+        framebuffer.scroll(4, 2);
+        assert!(framebuffer.get(4, 2));
+        assert_eq!(
+            framebuffer
+                .pixels()
+                .iter()
+                .filter(|pixel| **pixel != 0)
+                .count(),
+            1
+        );
+        framebuffer.scroll(-4, 0);
+        assert!(framebuffer.get(0, 2));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut framebuffer = Framebuffer::new(64, 32);
+        framebuffer.toggle(0, 0);
+        framebuffer.toggle(10, 20);
+        framebuffer.clear();
+        assert!(framebuffer.pixels().iter().all(|pixel| *pixel == 0));
+    }
+
+    #[test]
+    fn test_planes() {
+        let mut framebuffer = Framebuffer::new(64, 32);
+        framebuffer.toggle_plane(1, 1, SECOND_PLANE);
+        framebuffer.toggle_plane(2, 2, FIRST_PLANE);
+        framebuffer.toggle_plane(2, 2, SECOND_PLANE);
+        assert_eq!(framebuffer.planes(1, 1), 2);
+        assert_eq!(framebuffer.planes(2, 2), 3);
+
+        framebuffer.scroll_planes(1, 0, SECOND_PLANE);
+        assert_eq!(framebuffer.planes(2, 1), 2);
+        assert_eq!(framebuffer.planes(2, 2), 1);
+        assert_eq!(framebuffer.planes(3, 2), 2);
+
+        framebuffer.clear_planes(FIRST_PLANE);
+        assert_eq!(framebuffer.planes(2, 2), 0);
+        assert_eq!(framebuffer.planes(3, 2), 2);
+    }
+
+    #[test]
+    fn test_true_color() {
+        let mut framebuffer = Framebuffer::true_color(256, 192);
+        assert_eq!(Framebuffer::new(64, 32).color(0, 0), None);
+        framebuffer.set_color(0, 0, 200, [1, 2, 3]);
+        assert_eq!(framebuffer.planes(0, 0), 200);
+        framebuffer.scroll(0, 5);
+        assert_eq!(framebuffer.color(0, 5), Some([1, 2, 3]));
+        assert_eq!(framebuffer.planes(0, 5), 200);
+        assert_eq!(framebuffer.color(0, 0), Some([0, 0, 0]));
+        framebuffer.clear();
+        assert_eq!(framebuffer.planes(0, 5), 0);
+        assert_eq!(framebuffer.color(0, 5), Some([0, 0, 0]));
+    }
+}
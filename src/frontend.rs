@@ -0,0 +1,181 @@
+use winit::event::ElementState::Pressed;
+use winit::event::{Event, KeyboardInput, StartCause, WindowEvent};
+
+/// How many window pixels each CHIP-8 pixel takes up unless told otherwise.
+pub const DEFAULT_SCALE: u32 = 10;
+
+/// How the window looks and starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrontendOptions {
+    /// The colors of the background and the drawing planes.
+    pub palette: crate::palette::Palette,
+    /// The window starts this many times the size of the display.
+    pub scale: u32,
+    /// Whether the emulator starts paused. Space pauses and resumes.
+    pub paused: bool,
+}
+
+impl Default for FrontendOptions {
+    fn default() -> Self {
+        Self {
+            palette: crate::palette::Palette::default(),
+            scale: DEFAULT_SCALE,
+            paused: false,
+        }
+    }
+}
+
+/// Opens a window and runs the emulator inside a winit event loop, consuming it upon completion.
+/// The emulator itself never touches the renderer; the renderer reads the emulator's framebuffer before each redraw.
+///
+/// # Arguments:
+/// * `emulator`: The emulator to drive. Its audio sink plays the buzzer.
+/// * `options`: The palette, window scale and whether to start paused.
+pub async fn run(mut emulator: crate::emulator::Emulator, options: FrontendOptions) {
+    env_logger::init();
+    let event_loop = winit::event_loop::EventLoop::new();
+
+    let window: winit::window::Window = winit::window::WindowBuilder::new()
+        .with_inner_size(winit::dpi::LogicalSize::new(
+            (emulator.framebuffer().width() as u32 * options.scale) as f64,
+            (emulator.framebuffer().height() as u32 * options.scale) as f64,
+        ))
+        .build(&event_loop)
+        .unwrap();
+
+    let mut renderer = crate::renderer::RendererState::new(window, options.palette).await;
+    let mut paused = options.paused;
+    let mut scheduler = crate::scheduler::Scheduler::new();
+    let mut last_advance = std::time::Instant::now();
+
+    event_loop.run(move |event, _, control_flow| match event {
+        // wait a frame on init
+        Event::NewEvents(StartCause::Init) => {
+            last_advance = std::time::Instant::now();
+            control_flow.set_wait_until(last_advance + scheduler.frame_length());
+        }
+        // resizing window, closing window, user input
+        Event::WindowEvent {
+            window_id,
+            ref event,
+        } if window_id == renderer.window().id() => match event {
+            WindowEvent::CloseRequested
+            | WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
+                        ..
+                    },
+                ..
+            } => *control_flow = winit::event_loop::ControlFlow::Exit,
+            // Page Up / Page Down double or halve the CPU speed
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::PageUp),
+                        ..
+                    },
+                ..
+            } => {
+                let instructions_per_frame = emulator.config().instructions_per_frame;
+                emulator.set_instructions_per_frame((instructions_per_frame * 2).max(1));
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::PageDown),
+                        ..
+                    },
+                ..
+            } => {
+                let instructions_per_frame = emulator.config().instructions_per_frame;
+                emulator.set_instructions_per_frame((instructions_per_frame / 2).max(1));
+            }
+            // Space pauses and resumes
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: Pressed,
+                        virtual_keycode: Some(winit::event::VirtualKeyCode::Space),
+                        ..
+                    },
+                ..
+            } => paused = !paused,
+            // Resize
+            WindowEvent::Resized(physical_size) => {
+                renderer.resize(*physical_size);
+            }
+            // Scale factor changed
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                renderer.resize(**new_inner_size);
+            }
+            // Pressed or released a key
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state, scancode, ..
+                },
+                ..
+            } => emulator.handle_input(state, scancode),
+            _ => {}
+        },
+        // explicit redraw request
+        Event::RedrawRequested(window_id) if window_id == renderer.window().id() => {
+            renderer.update(emulator.framebuffer());
+            match renderer.render() {
+                Ok(_) => {}
+                Err(wgpu::SurfaceError::Lost) => renderer.resize(renderer.size),
+                Err(wgpu::SurfaceError::OutOfMemory) => {
+                    *control_flow = winit::event_loop::ControlFlow::Exit
+                }
+                Err(e) => eprint!("{:?}", e),
+            }
+        }
+        // everything else - run however many frames are due, no matter how often we were woken up
+        Event::MainEventsCleared => {
+            let now = std::time::Instant::now();
+            // time spent paused is skipped rather than caught up on
+            let frames = if paused {
+                0
+            } else {
+                scheduler.advance(now - last_advance)
+            };
+            last_advance = now;
+
+            for _ in 0..frames {
+                match emulator.run_frame() {
+                    Ok(outcome) if outcome.exited => {
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                        return;
+                    }
+                }
+            }
+
+            // render
+            if frames > 0 {
+                renderer.window().request_redraw();
+            }
+            control_flow.set_wait_until(now + scheduler.time_until_next_frame());
+        }
+        Event::LoopDestroyed => {
+            let stats = scheduler.stats();
+            log::info!(
+                "ran {} frames in {:?} ({} dropped, max lag {:?}, drift {:.3}s)",
+                stats.frames_run,
+                stats.elapsed,
+                stats.frames_dropped,
+                stats.max_lag,
+                stats.drift_seconds(scheduler.frame_length())
+            );
+        }
+        _ => {}
+    })
+}
@@ -0,0 +1,686 @@
+/// The port that `chip8 gdb` listens on unless given another, as in gdb's own examples.
+pub const DEFAULT_PORT: u16 = 1234;
+/// How many instructions continue runs between checks for an interrupt from the client.
+const CONTINUE_SLICE: u64 = 10_000;
+/// The largest packet the stub accepts, as told to the client in qSupported.
+const PACKET_SIZE: usize = 0x4000;
+/// The byte a client sends outside of a packet to interrupt a continue.
+const INTERRUPT: u8 = 0x03;
+/// The names and widths in bytes of the registers, in the order of the g packet.
+/// SP is the depth of the call stack. It can be read but not written.
+const REGISTERS: [(&str, usize); 21] = [
+    ("v0", 1),
+    ("v1", 1),
+    ("v2", 1),
+    ("v3", 1),
+    ("v4", 1),
+    ("v5", 1),
+    ("v6", 1),
+    ("v7", 1),
+    ("v8", 1),
+    ("v9", 1),
+    ("va", 1),
+    ("vb", 1),
+    ("vc", 1),
+    ("vd", 1),
+    ("ve", 1),
+    ("vf", 1),
+    ("i", 4),
+    ("pc", 4),
+    ("sp", 1),
+    ("dt", 1),
+    ("st", 1),
+];
+const INDEX_REGISTER: usize = 16;
+const PROGRAM_COUNTER: usize = 17;
+const STACK_POINTER: usize = 18;
+const DELAY_TIMER: usize = 19;
+
+/// What the client sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Incoming {
+    /// The body of a packet, without the framing and checksum.
+    Packet(String),
+    /// A request to stop a continue.
+    Interrupt,
+    /// The client hung up.
+    Closed,
+}
+
+/// Serves the GDB remote serial protocol for a debugger, so that gdb and lldb can attach to a ROM.
+/// Registers are sent little-endian, as gdb assumes for a target it has no architecture for.
+/// Memory is the whole address space of the platform, including the fonts below the start address.
+pub struct GdbStub {
+    debugger: crate::debugger::Debugger,
+    /// Set by QStartNoAckMode, after which packets are no longer acknowledged with + and -.
+    no_ack: bool,
+    /// The last packet sent, framed, for when the client asks for it again with -.
+    last_sent: Vec<u8>,
+    /// Answers ?, and starts as a SIGTRAP because the ROM is stopped before its first instruction.
+    last_stop: String,
+    killed: bool,
+}
+
+impl GdbStub {
+    pub fn new(debugger: crate::debugger::Debugger) -> Self {
+        Self {
+            debugger,
+            no_ack: false,
+            last_sent: Vec::new(),
+            last_stop: "S05".to_string(),
+            killed: false,
+        }
+    }
+
+    pub fn debugger(&self) -> &crate::debugger::Debugger {
+        &self.debugger
+    }
+
+    /// Whether a client sent k, after which there is nothing left to debug.
+    pub fn is_killed(&self) -> bool {
+        self.killed
+    }
+
+    /// Answers one client until it detaches, kills the ROM or hangs up.
+    pub fn serve(&mut self, stream: std::net::TcpStream) -> std::io::Result<()> {
+        stream.set_nodelay(true)?;
+        let mut reader = std::io::BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        self.no_ack = false;
+        loop {
+            let packet = match self.receive(&mut reader, &mut writer)? {
+                Incoming::Packet(packet) => packet,
+                // a stopped ROM has nothing to interrupt
+                Incoming::Interrupt => continue,
+                Incoming::Closed => return Ok(()),
+            };
+            let reply = match packet.as_bytes().first() {
+                Some(b'c') | Some(b's') => {
+                    if let Some(address) = packet.get(1..).filter(|address| !address.is_empty()) {
+                        match usize::from_str_radix(address, 16) {
+                            Ok(address) => {
+                                self.debugger.emulator_mut().set_program_counter(address)
+                            }
+                            Err(_) => {
+                                self.send(&mut writer, "E01")?;
+                                continue;
+                            }
+                        }
+                    }
+                    self.last_stop = if packet.starts_with('c') {
+                        self.resume(&mut reader)?
+                    } else {
+                        match self.debugger.step() {
+                            Ok(outcome) if outcome.exited => "W00".to_string(),
+                            Ok(_) => "S05".to_string(),
+                            Err(error) => Self::error_signal(&error).to_string(),
+                        }
+                    };
+                    self.last_stop.clone()
+                }
+                Some(b'D') => {
+                    self.send(&mut writer, "OK")?;
+                    return Ok(());
+                }
+                Some(b'k') => {
+                    self.killed = true;
+                    return Ok(());
+                }
+                _ if packet.starts_with("vKill") => {
+                    self.killed = true;
+                    self.send(&mut writer, "OK")?;
+                    return Ok(());
+                }
+                _ => self.respond(&packet),
+            };
+            self.send(&mut writer, &reply)?;
+        }
+    }
+
+    /// The reply to a packet that neither runs the ROM nor ends the session. Unknown packets get an empty reply.
+    fn respond(&mut self, packet: &str) -> String {
+        let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match command {
+            "?" => Some(self.last_stop.clone()),
+            "g" => Some(
+                (0..REGISTERS.len())
+                    .map(|number| self.encode_register(number))
+                    .collect(),
+            ),
+            "G" => self.write_registers(arguments),
+            "p" => usize::from_str_radix(arguments, 16)
+                .ok()
+                .filter(|number| *number < REGISTERS.len())
+                .map(|number| self.encode_register(number)),
+            "P" => arguments.split_once('=').and_then(|(number, value)| {
+                let number = usize::from_str_radix(number, 16).ok()?;
+                self.write_register(number, value).then(|| "OK".to_string())
+            }),
+            "m" => self.read_memory(arguments),
+            "M" => self.write_memory(arguments),
+            "Z" | "z" => self.toggle_breakpoint(command == "Z", arguments),
+            "H" => Some("OK".to_string()),
+            "q" | "Q" => return self.query(packet),
+            _ => return String::new(),
+        };
+        reply.unwrap_or_else(|| "E01".to_string())
+    }
+
+    /// The reply to a general query or set packet.
+    fn query(&mut self, packet: &str) -> String {
+        let (name, arguments) = packet.split_once(':').unwrap_or((packet, ""));
+        match name {
+            "qSupported" => format!(
+                "PacketSize={:x};qXfer:features:read+;QStartNoAckMode+",
+                PACKET_SIZE
+            ),
+            "QStartNoAckMode" => {
+                self.no_ack = true;
+                "OK".to_string()
+            }
+            // attached to an existing process, so that quitting gdb detaches instead of killing
+            "qAttached" => "1".to_string(),
+            "qXfer" => {
+                let Some(("features:read:target.xml", range)) = arguments.rsplit_once(':') else {
+                    return "E00".to_string();
+                };
+                let Some((offset, length)) = parse_range(range) else {
+                    return "E00".to_string();
+                };
+                let description = Self::target_description();
+                let start = offset.min(description.len());
+                let end = (start + length).min(description.len());
+                let more = if end < description.len() { "m" } else { "l" };
+                format!("{}{}", more, &description[start..end])
+            }
+            _ if name.starts_with("qRcmd,") => self.monitor(&name["qRcmd,".len()..]),
+            _ => String::new(),
+        }
+    }
+
+    /// Runs a `monitor` command, given in hex. `monitor key 5` presses a key and `monitor release 5` lets it go,
+    /// so that a ROM waiting for a key can be given one.
+    fn monitor(&mut self, command: &str) -> String {
+        let Some(command) = decode_hex(command).and_then(|bytes| String::from_utf8(bytes).ok())
+        else {
+            return "E01".to_string();
+        };
+        let state = match command.split_whitespace().next() {
+            Some("key") => winit::event::ElementState::Pressed,
+            Some("release") => winit::event::ElementState::Released,
+            _ => return encode_hex(b"monitor commands: key KEY, release KEY\n"),
+        };
+        let key = command
+            .split_whitespace()
+            .nth(1)
+            .and_then(|key| u8::from_str_radix(key.trim_start_matches("0x"), 16).ok());
+        match key.map(|key| self.debugger.emulator_mut().set_key(key, state)) {
+            Some(Ok(())) => "OK".to_string(),
+            Some(Err(error)) => encode_hex(format!("{}\n", error).as_bytes()),
+            None => encode_hex(b"expected a key from 0 to F\n"),
+        }
+    }
+
+    /// Runs until a breakpoint, the ROM stops by itself or the client interrupts, and returns the stop reply.
+    fn resume(
+        &mut self,
+        reader: &mut std::io::BufReader<std::net::TcpStream>,
+    ) -> std::io::Result<String> {
+        loop {
+            match self.debugger.run(CONTINUE_SLICE) {
+                crate::debugger::StopReason::CycleLimit => {}
+                crate::debugger::StopReason::Exited => return Ok("W00".to_string()),
+                crate::debugger::StopReason::Error(error) => {
+                    return Ok(Self::error_signal(&error).to_string())
+                }
+                // breakpoints, and a ROM waiting for a key that only `monitor key` can give it
+                _ => return Ok("S05".to_string()),
+            }
+            if Self::interrupted(reader)? {
+                return Ok("S02".to_string());
+            }
+        }
+    }
+
+    /// Whether the client sent an interrupt, or hung up, without waiting for it to send anything.
+    fn interrupted(reader: &mut std::io::BufReader<std::net::TcpStream>) -> std::io::Result<bool> {
+        use std::io::BufRead;
+        reader.get_ref().set_nonblocking(true)?;
+        let interrupted = loop {
+            match reader.fill_buf() {
+                Ok([]) => break Ok(true),
+                Ok([INTERRUPT, ..]) => {
+                    reader.consume(1);
+                    break Ok(true);
+                }
+                // acknowledgements of the continue packet
+                Ok(_) => reader.consume(1),
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => break Ok(false),
+                Err(error) => break Err(error),
+            }
+        };
+        reader.get_ref().set_nonblocking(false)?;
+        interrupted
+    }
+
+    /// The signal that a failed instruction stops the ROM with: SIGSEGV for memory, SIGILL otherwise.
+    fn error_signal(error: &crate::error::Chip8Error) -> &'static str {
+        match error {
+            crate::error::Chip8Error::AddressOutOfRange { .. } => "S0B",
+            _ => "S04",
+        }
+    }
+
+    fn register(&self, number: usize) -> u32 {
+        let emulator = self.debugger.emulator();
+        match number {
+            0..=15 => emulator.registers()[number] as u32,
+            INDEX_REGISTER => emulator.index_register(),
+            PROGRAM_COUNTER => emulator.program_counter() as u32,
+            STACK_POINTER => emulator.stack().len() as u32,
+            DELAY_TIMER => emulator.delay_timer().counter as u32,
+            // the sound timer
+            _ => emulator.sound_timer().counter as u32,
+        }
+    }
+
+    fn encode_register(&self, number: usize) -> String {
+        let width = REGISTERS[number].1;
+        encode_hex(&self.register(number).to_le_bytes()[..width])
+    }
+
+    /// Sets a register from its hex encoding. Returns false if the number or the value is invalid.
+    fn write_register(&mut self, number: usize, value: &str) -> bool {
+        let Some(&(_, width)) = REGISTERS.get(number) else {
+            return false;
+        };
+        let Some(bytes) = decode_hex(value).filter(|bytes| bytes.len() == width) else {
+            return false;
+        };
+        let mut value = [0; 4];
+        value[..width].copy_from_slice(&bytes);
+        let value = u32::from_le_bytes(value);
+        let emulator = self.debugger.emulator_mut();
+        match number {
+            0..=15 => emulator.set_register(number, value as u8),
+            INDEX_REGISTER => emulator.set_index_register(value as usize),
+            PROGRAM_COUNTER => emulator.set_program_counter(value as usize),
+            // the stack pointer can only be "written" with the value it already has
+            STACK_POINTER => return value as usize == emulator.stack().len(),
+            DELAY_TIMER => emulator.set_delay_timer(value as u8),
+            // the sound timer
+            _ => emulator.set_sound_timer(value as u8),
+        }
+        true
+    }
+
+    fn write_registers(&mut self, values: &str) -> Option<String> {
+        let total: usize = REGISTERS.iter().map(|(_, width)| width * 2).sum();
+        if values.len() != total {
+            return None;
+        }
+        // check the stack pointer first so that a rejected packet changes nothing
+        let mut start = 0;
+        let fields: Vec<(usize, &str)> = REGISTERS
+            .iter()
+            .enumerate()
+            .map(|(number, (_, width))| {
+                start += width * 2;
+                (number, &values[start - width * 2..start])
+            })
+            .collect();
+        let (_, stack_pointer) = fields[STACK_POINTER];
+        if decode_hex(stack_pointer)? != [self.debugger.emulator().stack().len() as u8] {
+            return None;
+        }
+        for (number, value) in fields {
+            if !self.write_register(number, value) {
+                return None;
+            }
+        }
+        Some("OK".to_string())
+    }
+
+    /// Reads `m ADDRESS,LENGTH`, stopping short at the end of memory.
+    fn read_memory(&self, arguments: &str) -> Option<String> {
+        let (address, length) = parse_range(arguments)?;
+        let memory = self.debugger.emulator().memory();
+        let bytes = memory.get(address..).filter(|bytes| !bytes.is_empty())?;
+        Some(encode_hex(
+            &bytes[..length.min(bytes.len()).min(PACKET_SIZE / 2)],
+        ))
+    }
+
+    /// Writes `M ADDRESS,LENGTH:BYTES`.
+    fn write_memory(&mut self, arguments: &str) -> Option<String> {
+        let (range, bytes) = arguments.split_once(':')?;
+        let (address, length) = parse_range(range)?;
+        let bytes = decode_hex(bytes).filter(|bytes| bytes.len() == length)?;
+        self.debugger.emulator_mut().poke(address, &bytes).ok()?;
+        Some("OK".to_string())
+    }
+
+    /// Sets or clears `Z0,ADDRESS,KIND`. Software breakpoints are the only kind, so other types get an empty reply.
+    fn toggle_breakpoint(&mut self, set: bool, arguments: &str) -> Option<String> {
+        let Some(breakpoint) = arguments.strip_prefix("0,") else {
+            return Some(String::new());
+        };
+        let (address, _kind) = breakpoint.split_once(',')?;
+        let address = usize::from_str_radix(address, 16).ok()?;
+        if set {
+            self.debugger.add_breakpoint(address);
+        } else {
+            self.debugger.remove_breakpoint(address);
+        }
+        Some("OK".to_string())
+    }
+
+    /// Describes the registers, so that gdb shows them by name.
+    fn target_description() -> String {
+        let registers: String = REGISTERS
+            .iter()
+            .map(|(name, width)| {
+                let kind = match *name {
+                    "pc" => "code_ptr",
+                    "i" => "data_ptr",
+                    _ => "uint8",
+                };
+                format!(
+                    "<reg name=\"{}\" bitsize=\"{}\" type=\"{}\"/>",
+                    name,
+                    width * 8,
+                    kind
+                )
+            })
+            .collect();
+        format!(
+            "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\">\
+             <target version=\"1.0\"><feature name=\"org.chip8.core\">{}</feature></target>",
+            registers
+        )
+    }
+
+    /// Reads up to the next packet or interrupt, acknowledging packets and resending the last one when asked.
+    fn receive(
+        &mut self,
+        reader: &mut std::io::BufReader<std::net::TcpStream>,
+        writer: &mut std::net::TcpStream,
+    ) -> std::io::Result<Incoming> {
+        use std::io::{BufRead, Read, Write};
+        let mut byte = [0];
+        loop {
+            if reader.read(&mut byte)? == 0 {
+                return Ok(Incoming::Closed);
+            }
+            match byte[0] {
+                INTERRUPT => return Ok(Incoming::Interrupt),
+                b'-' if !self.no_ack => writer.write_all(&self.last_sent)?,
+                b'$' => {
+                    let mut body = Vec::new();
+                    reader.read_until(b'#', &mut body)?;
+                    let mut checksum = [0; 2];
+                    if body.pop() != Some(b'#') || reader.read_exact(&mut checksum).is_err() {
+                        return Ok(Incoming::Closed);
+                    }
+                    let valid = std::str::from_utf8(&checksum)
+                        .ok()
+                        .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                        == Some(checksum_of(&body));
+                    if !self.no_ack {
+                        writer.write_all(if valid { b"+" } else { b"-" })?;
+                    }
+                    if valid || self.no_ack {
+                        return Ok(Incoming::Packet(
+                            String::from_utf8_lossy(&body).into_owned(),
+                        ));
+                    }
+                }
+                // acknowledgements, and anything else between packets
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, writer: &mut std::net::TcpStream, body: &str) -> std::io::Result<()> {
+        use std::io::Write;
+        let mut packet = vec![b'$'];
+        for byte in body.bytes() {
+            match byte {
+                b'$' | b'#' | b'}' | b'*' => packet.extend([b'}', byte ^ 0x20]),
+                _ => packet.push(byte),
+            }
+        }
+        let checksum = checksum_of(&packet[1..]);
+        packet.extend(format!("#{:02x}", checksum).bytes());
+        self.last_sent = packet;
+        writer.write_all(&self.last_sent)
+    }
+}
+
+/// Serves one client after another on the listener until one kills the ROM.
+pub fn run(
+    debugger: crate::debugger::Debugger,
+    listener: std::net::TcpListener,
+) -> std::io::Result<()> {
+    let mut stub = GdbStub::new(debugger);
+    while !stub.is_killed() {
+        let (stream, _) = listener.accept()?;
+        stub.serve(stream)?;
+    }
+    Ok(())
+}
+
+fn checksum_of(body: &[u8]) -> u8 {
+    body.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect()
+}
+
+/// Parses `ADDRESS,LENGTH` in hex.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (address, length) = range.split_once(',')?;
+    Some((
+        usize::from_str_radix(address, 16).ok()?,
+        usize::from_str_radix(length, 16).ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod gdb_tests {
+    use super::GdbStub;
+    use crate::debugger::Debugger;
+    use crate::emulator::Emulator;
+    use std::io::{BufRead, Read, Write};
+
+    /// A loopback client that speaks just enough of the protocol to drive the stub.
+    struct Client {
+        stream: std::net::TcpStream,
+        reader: std::io::BufReader<std::net::TcpStream>,
+        no_ack: bool,
+    }
+
+    impl Client {
+        fn send(&mut self, body: &str) {
+            let packet = format!("${}#{:02x}", body, super::checksum_of(body.as_bytes()));
+            self.stream.write_all(packet.as_bytes()).unwrap();
+        }
+
+        fn receive(&mut self) -> String {
+            let mut skipped = Vec::new();
+            self.reader.read_until(b'$', &mut skipped).unwrap();
+            // everything before the packet is an acknowledgement
+            assert!(skipped[..skipped.len() - 1]
+                .iter()
+                .all(|byte| *byte == b'+'));
+            let mut body = Vec::new();
+            self.reader.read_until(b'#', &mut body).unwrap();
+            body.pop();
+            let mut checksum = [0; 2];
+            self.reader.read_exact(&mut checksum).unwrap();
+            assert_eq!(
+                u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap(),
+                super::checksum_of(&body)
+            );
+            if !self.no_ack {
+                self.stream.write_all(b"+").unwrap();
+            }
+            String::from_utf8(body).unwrap()
+        }
+
+        fn request(&mut self, body: &str) -> String {
+            self.send(body);
+            self.receive()
+        }
+    }
+
+    /// Serves one connection from a client running on another thread, and fails if the client does.
+    fn serve(stub: &mut GdbStub, client: impl FnOnce(&mut Client) + Send + 'static) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let stream = std::net::TcpStream::connect(address).unwrap();
+            stream.set_nodelay(true).unwrap();
+            client(&mut Client {
+                reader: std::io::BufReader::new(stream.try_clone().unwrap()),
+                stream,
+                no_ack: false,
+            });
+        });
+        let (stream, _) = listener.accept().unwrap();
+        stub.serve(stream).unwrap();
+        client.join().unwrap();
+    }
+
+    const SOURCE: &str = "
+        : main
+            v0 := 5
+            i := 0x300
+            draw
+        : end
+            jump end
+        : draw
+            v1 := 1
+            return
+    ";
+
+    #[test]
+    fn test_registers_and_memory() {
+        let mut stub = GdbStub::new(Debugger::new(Emulator::assembled(SOURCE)));
+        serve(&mut stub, |client| {
+            assert!(client
+                .request("qSupported:swbreak+")
+                .contains("qXfer:features:read+"));
+            assert_eq!(client.request("?"), "S05");
+            // V0 to VF, then I, PC, SP, DT and ST
+            let registers = format!(
+                "{}{}{}{}",
+                "00".repeat(16),
+                "00000000",
+                "00020000",
+                "000000"
+            );
+            assert_eq!(client.request("g"), registers);
+            assert_eq!(client.request("p11"), "00020000");
+            assert_eq!(client.request("P3=2a"), "OK");
+            assert_eq!(client.request("p3"), "2a");
+            assert_eq!(client.request("P13=3c"), "OK");
+            // the stack pointer is read-only
+            assert_eq!(client.request("P12=01"), "E01");
+            assert_eq!(client.request("p15"), "E01");
+
+            assert_eq!(client.request("QStartNoAckMode"), "OK");
+            client.no_ack = true;
+            assert_eq!(client.request("m200,4"), "6005a300");
+            assert_eq!(client.request("M300,2:abcd"), "OK");
+            assert_eq!(client.request("m300,2"), "abcd");
+            // reads stop short at the end of memory, and fail past it
+            assert_eq!(client.request("mffe,4"), "0000");
+            assert_eq!(client.request("m1000,1"), "E01");
+            assert_eq!(client.request("Mfff,2:0000"), "E01");
+
+            let description = client.request("qXfer:features:read:target.xml:0,fff");
+            assert!(description.starts_with("l<?xml"));
+            assert!(description.contains("<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\"/>"));
+            assert_eq!(
+                client.request("qXfer:features:read:target.xml:0,10").len(),
+                17
+            );
+            assert_eq!(client.request("vMustReplyEmpty"), "");
+            assert_eq!(client.request("D"), "OK");
+        });
+        let emulator = stub.debugger().emulator();
+        assert_eq!(emulator.registers()[3], 0x2A);
+        assert_eq!(emulator.delay_timer().counter, 0x3C);
+        assert_eq!(emulator.memory()[0x300..0x302], [0xAB, 0xCD]);
+        assert!(!stub.is_killed());
+    }
+
+    #[test]
+    fn test_breakpoints_and_step() {
+        let mut stub = GdbStub::new(Debugger::new(Emulator::assembled(SOURCE)));
+        serve(&mut stub, |client| {
+            assert_eq!(client.request("Z0,206,2"), "OK");
+            assert_eq!(client.request("c"), "S05");
+            assert_eq!(client.request("p11"), "06020000");
+            // the subroutine has run and returned
+            assert_eq!(client.request("p1"), "01");
+            assert_eq!(client.request("p12"), "00");
+
+            // continuing from a breakpoint moves past it and comes back round
+            assert_eq!(client.request("c"), "S05");
+            assert_eq!(client.request("p11"), "06020000");
+            assert_eq!(client.request("z0,206,2"), "OK");
+
+            assert_eq!(client.request("s208"), "S05");
+            assert_eq!(client.request("p11"), "0a020000");
+            assert_eq!(client.request("s"), "S04");
+            assert_eq!(client.request("?"), "S04");
+            // watchpoints are not supported
+            assert_eq!(client.request("Z2,300,1"), "");
+            client.send("k");
+        });
+        assert_eq!(stub.debugger().breakpoints().count(), 0);
+        assert!(stub.is_killed());
+    }
+
+    #[test]
+    fn test_interrupt_and_monitor() {
+        let mut stub = GdbStub::new(Debugger::new(Emulator::assembled(
+            "v3 := key : end jump end",
+        )));
+        serve(&mut stub, |client| {
+            // stops for a key, which only the monitor can press
+            assert_eq!(client.request("c"), "S05");
+            assert_eq!(client.request("p11"), "00020000");
+            let command = |text: &str| format!("qRcmd,{}", super::encode_hex(text.as_bytes()));
+            assert_eq!(client.request(&command("key b")), "OK");
+            assert_eq!(client.request(&command("release b")), "OK");
+            assert_eq!(client.request("p3"), "0b");
+            assert_eq!(
+                client.request(&command("key 10")),
+                super::encode_hex(b"invalid key 0x10\n")
+            );
+
+            client.send("c");
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            client.stream.write_all(&[super::INTERRUPT]).unwrap();
+            assert_eq!(client.receive(), "S02");
+            assert_eq!(client.request("p11"), "02020000");
+        });
+        assert!(stub.debugger().cycles() > 1);
+    }
+}
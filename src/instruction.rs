@@ -0,0 +1,620 @@
+/// A decoded CHIP-8 instruction. Names follow Cowgod's technical reference.
+/// X and Y are register numbers, NN is a byte, NNN is an address and N is a nibble.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// 0NNN: Calls a machine code routine. Ignored by modern interpreters.
+    Sys(u16),
+    /// 0010: Switches off the MEGA-CHIP mode (MEGA-CHIP).
+    MegaOff,
+    /// 0011: Switches to the 256x192 MEGA-CHIP color mode (MEGA-CHIP).
+    MegaOn,
+    /// 01NN NNNN: Sets I to the 24-bit address NN followed by the next two bytes (MEGA-CHIP).
+    Ldhi(u8),
+    /// 02NN: Loads NN ARGB colors from I into the palette, starting at index 1 (MEGA-CHIP).
+    Ldpal(u8),
+    /// 03NN: Sets the sprite width to NN, or 256 if NN is 0 (MEGA-CHIP).
+    Sprw(u8),
+    /// 04NN: Sets the sprite height to NN, or 256 if NN is 0 (MEGA-CHIP).
+    Sprh(u8),
+    /// 05NN: Sets the screen's opacity to NN (MEGA-CHIP).
+    Alpha(u8),
+    /// 060N: Plays the digitized sound at I, looping if N is 0 (MEGA-CHIP).
+    Digisnd(u8),
+    /// 0700: Stops the digitized sound (MEGA-CHIP).
+    Stopsnd,
+    /// 080N: Sets how sprites blend with the screen (MEGA-CHIP).
+    Bmode(u8),
+    /// 09NN: Sets the palette index that causes collisions (MEGA-CHIP).
+    Ccol(u8),
+    /// 00BN: Scrolls the display up by N pixels (MEGA-CHIP).
+    Scru(u8),
+    /// 0230: Clears the 64x64 display (two-page hi-res VIP).
+    ClsHires,
+    /// 02A0: Cycles the background color through blue, black, green and red (CHIP-8X).
+    Bgcol,
+    /// 00CN: Scrolls the display down by N pixels (SUPER-CHIP).
+    Scd(u8),
+    /// 00DN: Scrolls the display up by N pixels (XO-CHIP).
+    Scu(u8),
+    /// 00E0: Clears the screen.
+    Cls,
+    /// 00EE: Returns from a subroutine.
+    Ret,
+    /// 00FB: Scrolls the display right by 4 pixels (SUPER-CHIP).
+    Scr,
+    /// 00FC: Scrolls the display left by 4 pixels (SUPER-CHIP).
+    Scl,
+    /// 00FD: Exits the interpreter (SUPER-CHIP).
+    Exit,
+    /// 00FE: Switches to the 64x32 low resolution mode (SUPER-CHIP).
+    Low,
+    /// 00FF: Switches to the 128x64 high resolution mode (SUPER-CHIP).
+    High,
+    /// 1NNN: Jumps to NNN.
+    Jp(u16),
+    /// 2NNN: Calls the subroutine at NNN.
+    Call(u16),
+    /// 3XNN: Skips the next instruction if VX == NN.
+    SeByte { x: u8, byte: u8 },
+    /// 4XNN: Skips the next instruction if VX != NN.
+    SneByte { x: u8, byte: u8 },
+    /// 5XY0: Skips the next instruction if VX == VY.
+    SeReg { x: u8, y: u8 },
+    /// 5XY2: Stores VX to VY in memory starting at I, without changing I (XO-CHIP).
+    SaveRange { x: u8, y: u8 },
+    /// 5XY3: Loads VX to VY from memory starting at I, without changing I (XO-CHIP).
+    LoadRange { x: u8, y: u8 },
+    /// 6XNN: Sets VX to NN.
+    LdByte { x: u8, byte: u8 },
+    /// 7XNN: Adds NN to VX without setting the carry flag.
+    AddByte { x: u8, byte: u8 },
+    /// 8XY0: Sets VX to VY.
+    LdReg { x: u8, y: u8 },
+    /// 8XY1: Sets VX to VX | VY.
+    Or { x: u8, y: u8 },
+    /// 8XY2: Sets VX to VX & VY.
+    And { x: u8, y: u8 },
+    /// 8XY3: Sets VX to VX ^ VY.
+    Xor { x: u8, y: u8 },
+    /// 8XY4: Sets VX to VX + VY and VF to the carry.
+    AddReg { x: u8, y: u8 },
+    /// 8XY5: Sets VX to VX - VY and VF to NOT borrow.
+    Sub { x: u8, y: u8 },
+    /// 8XY6: Shifts VX right by one and sets VF to the bit shifted out.
+    Shr { x: u8, y: u8 },
+    /// 8XY7: Sets VX to VY - VX and VF to NOT borrow.
+    Subn { x: u8, y: u8 },
+    /// 8XYE: Shifts VX left by one and sets VF to the bit shifted out.
+    Shl { x: u8, y: u8 },
+    /// 9XY0: Skips the next instruction if VX != VY.
+    SneReg { x: u8, y: u8 },
+    /// ANNN: Sets I to NNN.
+    LdI(u16),
+    /// BNNN: Jumps to NNN + V0 (or XNN + VX, depending on the interpreter).
+    JpV0(u16),
+    /// BXY0: Colors the 8x4 pixel zones given by VX and VX+1 with the color in VY.
+    /// BXYN: Colors N rows of the 8 pixel columns at (VX, VY) with the color in VX+1 (CHIP-8X).
+    Col { x: u8, y: u8, n: u8 },
+    /// CXNN: Sets VX to a random byte ANDed with NN.
+    Rnd { x: u8, byte: u8 },
+    /// DXYN: Draws an N-row sprite from memory at I to (VX, VY). DXY0 draws a 16x16 sprite in SUPER-CHIP.
+    Drw { x: u8, y: u8, n: u8 },
+    /// EX9E: Skips the next instruction if the key in VX is pressed.
+    Skp(u8),
+    /// EXA1: Skips the next instruction if the key in VX is not pressed.
+    Sknp(u8),
+    /// EXF2: Skips the next instruction if the key in VX is pressed on the second keypad (CHIP-8X).
+    SkpSecond(u8),
+    /// EXF5: Skips the next instruction if the key in VX is not pressed on the second keypad (CHIP-8X).
+    SknpSecond(u8),
+    /// FN01: Selects the planes that drawing, clearing and scrolling affect (XO-CHIP).
+    Plane(u8),
+    /// F002: Loads the 16-byte audio pattern at I (XO-CHIP).
+    Audio,
+    /// F000 NNNN: Sets I to the 16-bit address in the following two bytes (XO-CHIP).
+    /// Like MEGA-CHIP's 01NN, this instruction is 4 bytes long.
+    LdILong,
+    /// FX07: Sets VX to the delay timer.
+    LdVxDt(u8),
+    /// FX0A: Waits for a key and stores it in VX.
+    LdVxK(u8),
+    /// FX15: Sets the delay timer to VX.
+    LdDtVx(u8),
+    /// FX18: Sets the sound timer to VX.
+    LdStVx(u8),
+    /// FX1E: Adds VX to I.
+    AddI(u8),
+    /// FX29: Points I at the font character for the low nibble of VX.
+    LdF(u8),
+    /// FX30: Points I at the 10-byte big font character for the low nibble of VX (SUPER-CHIP).
+    LdHf(u8),
+    /// FX75: Saves V0 to VX in the RPL user flags (SUPER-CHIP).
+    LdRVx(u8),
+    /// FX85: Loads V0 to VX from the RPL user flags (SUPER-CHIP).
+    LdVxR(u8),
+    /// FX3A: Sets the audio pattern's playback pitch to VX (XO-CHIP).
+    Pitch(u8),
+    /// FX33: Stores the binary-coded decimal of VX at I, I + 1 and I + 2.
+    LdBcd(u8),
+    /// FX55: Stores V0 to VX in memory starting at I.
+    LdIVx(u8),
+    /// FX65: Loads V0 to VX from memory starting at I.
+    LdVxI(u8),
+}
+
+/// Why an opcode could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownOpcode(u16),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnknownOpcode(opcode) => write!(f, "unknown opcode {:04X}", opcode),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Instruction {
+    /// How many bytes the instruction takes up in memory, including any operand words that follow it.
+    pub fn length(&self) -> u16 {
+        match self {
+            Instruction::LdILong | Instruction::Ldhi(_) => 4,
+            _ => 2,
+        }
+    }
+}
+
+/// The instructions an interpreter understands beyond the original CHIP-8 set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionSet {
+    /// The original COSMAC VIP instructions.
+    Chip8,
+    /// CHIP-8 plus the SUPER-CHIP 1.1 scrolling, resolution and big font instructions.
+    SuperChip,
+    /// SUPER-CHIP plus Octo's XO-CHIP extensions and 64 KiB of memory.
+    XoChip,
+    /// SUPER-CHIP plus the MEGA-CHIP color, sound and 24-bit memory extensions.
+    MegaChip,
+    /// The COSMAC VIP's two-page interpreter, with a 64x64 display.
+    TwoPageHires,
+    /// The COSMAC VIP with the VP-590 color board and a second keypad.
+    Chip8X,
+}
+
+impl InstructionSet {
+    /// Whether an interpreter with this instruction set can execute the instruction.
+    pub fn supports(&self, instruction: &Instruction) -> bool {
+        let super_chip = matches!(
+            instruction,
+            Instruction::Scd(_)
+                | Instruction::Scr
+                | Instruction::Scl
+                | Instruction::Exit
+                | Instruction::Low
+                | Instruction::High
+                | Instruction::LdHf(_)
+                | Instruction::LdRVx(_)
+                | Instruction::LdVxR(_)
+        );
+        let xo_chip = matches!(
+            instruction,
+            Instruction::Scu(_)
+                | Instruction::SaveRange { .. }
+                | Instruction::LoadRange { .. }
+                | Instruction::LdILong
+                | Instruction::Plane(_)
+                | Instruction::Audio
+                | Instruction::Pitch(_)
+        );
+        let mega_chip = matches!(
+            instruction,
+            Instruction::MegaOff
+                | Instruction::MegaOn
+                | Instruction::Ldhi(_)
+                | Instruction::Ldpal(_)
+                | Instruction::Sprw(_)
+                | Instruction::Sprh(_)
+                | Instruction::Alpha(_)
+                | Instruction::Digisnd(_)
+                | Instruction::Stopsnd
+                | Instruction::Bmode(_)
+                | Instruction::Ccol(_)
+                | Instruction::Scru(_)
+        );
+        let two_page = matches!(instruction, Instruction::ClsHires);
+        let chip8x = matches!(
+            instruction,
+            Instruction::Bgcol
+                | Instruction::Col { .. }
+                | Instruction::SkpSecond(_)
+                | Instruction::SknpSecond(_)
+        );
+        let extension = super_chip || xo_chip || mega_chip || two_page || chip8x;
+        match self {
+            InstructionSet::Chip8 => !extension,
+            InstructionSet::SuperChip => !extension || super_chip,
+            InstructionSet::XoChip => !extension || super_chip || xo_chip,
+            InstructionSet::MegaChip => !extension || super_chip || mega_chip,
+            InstructionSet::TwoPageHires => !extension || two_page,
+            InstructionSet::Chip8X => {
+                (!extension || chip8x) && !matches!(instruction, Instruction::JpV0(_))
+            }
+        }
+    }
+
+    /// How many RPL user flags FX75 and FX85 can access.
+    pub fn flag_count(&self) -> usize {
+        match self {
+            InstructionSet::Chip8 | InstructionSet::TwoPageHires | InstructionSet::Chip8X => 0,
+            InstructionSet::SuperChip | InstructionSet::MegaChip => 8,
+            InstructionSet::XoChip => 16,
+        }
+    }
+
+    /// How many bytes of memory the interpreter has.
+    pub const fn memory_size(&self) -> usize {
+        match self {
+            InstructionSet::Chip8
+            | InstructionSet::SuperChip
+            | InstructionSet::TwoPageHires
+            | InstructionSet::Chip8X => 4096,
+            InstructionSet::XoChip => 65536,
+            InstructionSet::MegaChip => crate::megachip::MEMORY_SIZE,
+        }
+    }
+}
+
+/// Decodes a two-byte opcode into an instruction.
+pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+    let p = crate::instruction_format::InstructionFormat::new(&opcode.to_be_bytes());
+    let x = p.second_nibble as u8;
+    let y = p.third_nibble as u8;
+    let byte = p.nibbles_3_to_4;
+    let address = p.nibbles_2_to_4 as u16;
+
+    let instruction = match p.first_nibble {
+        0x0 => match opcode {
+            0x0010 => Instruction::MegaOff,
+            0x0011 => Instruction::MegaOn,
+            0x00B0..=0x00BF => Instruction::Scru(p.fourth_nibble as u8),
+            0x0100..=0x01FF => Instruction::Ldhi(byte),
+            0x0200..=0x02FF => Instruction::Ldpal(byte),
+            0x0300..=0x03FF => Instruction::Sprw(byte),
+            0x0400..=0x04FF => Instruction::Sprh(byte),
+            0x0500..=0x05FF => Instruction::Alpha(byte),
+            0x0600..=0x060F => Instruction::Digisnd(p.fourth_nibble as u8),
+            0x0700 => Instruction::Stopsnd,
+            0x0800..=0x0805 => Instruction::Bmode(p.fourth_nibble as u8),
+            0x0900..=0x09FF => Instruction::Ccol(byte),
+            0x00C0..=0x00CF => Instruction::Scd(p.fourth_nibble as u8),
+            0x00D0..=0x00DF => Instruction::Scu(p.fourth_nibble as u8),
+            0x00E0 => Instruction::Cls,
+            0x00EE => Instruction::Ret,
+            0x00FB => Instruction::Scr,
+            0x00FC => Instruction::Scl,
+            0x00FD => Instruction::Exit,
+            0x00FE => Instruction::Low,
+            0x00FF => Instruction::High,
+            _ => Instruction::Sys(address),
+        },
+        0x1 => Instruction::Jp(address),
+        0x2 => Instruction::Call(address),
+        0x3 => Instruction::SeByte { x, byte },
+        0x4 => Instruction::SneByte { x, byte },
+        0x5 => match p.fourth_nibble {
+            0x0 => Instruction::SeReg { x, y },
+            0x2 => Instruction::SaveRange { x, y },
+            0x3 => Instruction::LoadRange { x, y },
+            _ => return Err(DecodeError::UnknownOpcode(opcode)),
+        },
+        0x6 => Instruction::LdByte { x, byte },
+        0x7 => Instruction::AddByte { x, byte },
+        0x8 => match p.fourth_nibble {
+            0x0 => Instruction::LdReg { x, y },
+            0x1 => Instruction::Or { x, y },
+            0x2 => Instruction::And { x, y },
+            0x3 => Instruction::Xor { x, y },
+            0x4 => Instruction::AddReg { x, y },
+            0x5 => Instruction::Sub { x, y },
+            0x6 => Instruction::Shr { x, y },
+            0x7 => Instruction::Subn { x, y },
+            0xE => Instruction::Shl { x, y },
+            _ => return Err(DecodeError::UnknownOpcode(opcode)),
+        },
+        0x9 if p.fourth_nibble == 0x0 => Instruction::SneReg { x, y },
+        0xA => Instruction::LdI(address),
+        0xB => Instruction::JpV0(address),
+        0xC => Instruction::Rnd { x, byte },
+        0xD => Instruction::Drw {
+            x,
+            y,
+            n: p.fourth_nibble as u8,
+        },
+        0xE => match byte {
+            0x9E => Instruction::Skp(x),
+            0xA1 => Instruction::Sknp(x),
+            0xF2 => Instruction::SkpSecond(x),
+            0xF5 => Instruction::SknpSecond(x),
+            _ => return Err(DecodeError::UnknownOpcode(opcode)),
+        },
+        0xF => match byte {
+            0x00 if x == 0x0 => Instruction::LdILong,
+            0x01 => Instruction::Plane(x),
+            0x02 if x == 0x0 => Instruction::Audio,
+            0x07 => Instruction::LdVxDt(x),
+            0x0A => Instruction::LdVxK(x),
+            0x15 => Instruction::LdDtVx(x),
+            0x18 => Instruction::LdStVx(x),
+            0x1E => Instruction::AddI(x),
+            0x29 => Instruction::LdF(x),
+            0x30 => Instruction::LdHf(x),
+            0x3A => Instruction::Pitch(x),
+            0x33 => Instruction::LdBcd(x),
+            0x55 => Instruction::LdIVx(x),
+            0x65 => Instruction::LdVxI(x),
+            0x75 => Instruction::LdRVx(x),
+            0x85 => Instruction::LdVxR(x),
+            _ => return Err(DecodeError::UnknownOpcode(opcode)),
+        },
+        _ => return Err(DecodeError::UnknownOpcode(opcode)),
+    };
+    Ok(instruction)
+}
+
+/// Decodes an opcode for an interpreter with the given instruction set.
+/// Extension opcodes in the 0NNN range that the set does not support are machine code calls, as they were on the original hardware.
+/// This also decodes the VIP variants' opcodes that clash with other extensions, which `decode` leaves to them.
+pub fn decode_for(
+    opcode: u16,
+    instruction_set: InstructionSet,
+) -> Result<Instruction, DecodeError> {
+    let p = crate::instruction_format::InstructionFormat::new(&opcode.to_be_bytes());
+    let instruction = match (instruction_set, opcode) {
+        (InstructionSet::TwoPageHires, 0x0230) => Instruction::ClsHires,
+        (InstructionSet::Chip8X, 0x02A0) => Instruction::Bgcol,
+        (InstructionSet::Chip8X, 0xB000..=0xBFFF) => Instruction::Col {
+            x: p.second_nibble as u8,
+            y: p.third_nibble as u8,
+            n: p.fourth_nibble as u8,
+        },
+        _ => decode(opcode)?,
+    };
+    if instruction_set.supports(&instruction) {
+        Ok(instruction)
+    } else if opcode >> 12 == 0x0 {
+        Ok(Instruction::Sys(opcode))
+    } else {
+        Err(DecodeError::UnknownOpcode(opcode))
+    }
+}
+
+/// Encodes an instruction back into its two-byte opcode.
+pub fn encode(instruction: &Instruction) -> u16 {
+    let xy = |prefix: u16, x: u8, y: u8, suffix: u16| {
+        (prefix << 12) | ((x as u16 & 0xF) << 8) | ((y as u16 & 0xF) << 4) | suffix
+    };
+    let xnn = |prefix: u16, x: u8, byte: u8| (prefix << 12) | ((x as u16 & 0xF) << 8) | byte as u16;
+    let nnn = |prefix: u16, address: u16| (prefix << 12) | (address & 0xFFF);
+
+    match *instruction {
+        Instruction::Sys(address) => nnn(0x0, address),
+        Instruction::MegaOff => 0x0010,
+        Instruction::MegaOn => 0x0011,
+        Instruction::Ldhi(byte) => 0x0100 | byte as u16,
+        Instruction::Ldpal(byte) => 0x0200 | byte as u16,
+        Instruction::Sprw(byte) => 0x0300 | byte as u16,
+        Instruction::Sprh(byte) => 0x0400 | byte as u16,
+        Instruction::Alpha(byte) => 0x0500 | byte as u16,
+        Instruction::Digisnd(n) => 0x0600 | (n as u16 & 0xF),
+        Instruction::Stopsnd => 0x0700,
+        Instruction::Bmode(n) => 0x0800 | (n as u16 & 0xF),
+        Instruction::Ccol(byte) => 0x0900 | byte as u16,
+        Instruction::Scru(n) => 0x00B0 | (n as u16 & 0xF),
+        Instruction::ClsHires => 0x0230,
+        Instruction::Bgcol => 0x02A0,
+        Instruction::Col { x, y, n } => xy(0xB, x, y, n as u16 & 0xF),
+        Instruction::SkpSecond(x) => xnn(0xE, x, 0xF2),
+        Instruction::SknpSecond(x) => xnn(0xE, x, 0xF5),
+        Instruction::Scd(n) => 0x00C0 | (n as u16 & 0xF),
+        Instruction::Scu(n) => 0x00D0 | (n as u16 & 0xF),
+        Instruction::Cls => 0x00E0,
+        Instruction::Ret => 0x00EE,
+        Instruction::Scr => 0x00FB,
+        Instruction::Scl => 0x00FC,
+        Instruction::Exit => 0x00FD,
+        Instruction::Low => 0x00FE,
+        Instruction::High => 0x00FF,
+        Instruction::Jp(address) => nnn(0x1, address),
+        Instruction::Call(address) => nnn(0x2, address),
+        Instruction::SeByte { x, byte } => xnn(0x3, x, byte),
+        Instruction::SneByte { x, byte } => xnn(0x4, x, byte),
+        Instruction::SeReg { x, y } => xy(0x5, x, y, 0x0),
+        Instruction::SaveRange { x, y } => xy(0x5, x, y, 0x2),
+        Instruction::LoadRange { x, y } => xy(0x5, x, y, 0x3),
+        Instruction::LdByte { x, byte } => xnn(0x6, x, byte),
+        Instruction::AddByte { x, byte } => xnn(0x7, x, byte),
+        Instruction::LdReg { x, y } => xy(0x8, x, y, 0x0),
+        Instruction::Or { x, y } => xy(0x8, x, y, 0x1),
+        Instruction::And { x, y } => xy(0x8, x, y, 0x2),
+        Instruction::Xor { x, y } => xy(0x8, x, y, 0x3),
+        Instruction::AddReg { x, y } => xy(0x8, x, y, 0x4),
+        Instruction::Sub { x, y } => xy(0x8, x, y, 0x5),
+        Instruction::Shr { x, y } => xy(0x8, x, y, 0x6),
+        Instruction::Subn { x, y } => xy(0x8, x, y, 0x7),
+        Instruction::Shl { x, y } => xy(0x8, x, y, 0xE),
+        Instruction::SneReg { x, y } => xy(0x9, x, y, 0x0),
+        Instruction::LdI(address) => nnn(0xA, address),
+        Instruction::JpV0(address) => nnn(0xB, address),
+        Instruction::Rnd { x, byte } => xnn(0xC, x, byte),
+        Instruction::Drw { x, y, n } => xy(0xD, x, y, n as u16 & 0xF),
+        Instruction::Skp(x) => xnn(0xE, x, 0x9E),
+        Instruction::Sknp(x) => xnn(0xE, x, 0xA1),
+        Instruction::LdILong => 0xF000,
+        Instruction::Plane(n) => xnn(0xF, n, 0x01),
+        Instruction::Audio => 0xF002,
+        Instruction::LdVxDt(x) => xnn(0xF, x, 0x07),
+        Instruction::LdVxK(x) => xnn(0xF, x, 0x0A),
+        Instruction::LdDtVx(x) => xnn(0xF, x, 0x15),
+        Instruction::LdStVx(x) => xnn(0xF, x, 0x18),
+        Instruction::AddI(x) => xnn(0xF, x, 0x1E),
+        Instruction::LdF(x) => xnn(0xF, x, 0x29),
+        Instruction::LdHf(x) => xnn(0xF, x, 0x30),
+        Instruction::Pitch(x) => xnn(0xF, x, 0x3A),
+        Instruction::LdBcd(x) => xnn(0xF, x, 0x33),
+        Instruction::LdIVx(x) => xnn(0xF, x, 0x55),
+        Instruction::LdVxI(x) => xnn(0xF, x, 0x65),
+        Instruction::LdRVx(x) => xnn(0xF, x, 0x75),
+        Instruction::LdVxR(x) => xnn(0xF, x, 0x85),
+    }
+}
+
+#[cfg(test)]
+mod instruction_tests {
+    use super::{decode, decode_for, encode, DecodeError, Instruction, InstructionSet};
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(0x00E0), Ok(Instruction::Cls));
+        assert_eq!(decode(0x00EE), Ok(Instruction::Ret));
+        assert_eq!(decode(0x0A23), Ok(Instruction::Sys(0xA23)));
+        assert_eq!(decode(0x1ABC), Ok(Instruction::Jp(0xABC)));
+        assert_eq!(
+            decode(0x3A42),
+            Ok(Instruction::SeByte { x: 0xA, byte: 0x42 })
+        );
+        assert_eq!(decode(0x8AB6), Ok(Instruction::Shr { x: 0xA, y: 0xB }));
+        assert_eq!(decode(0xD125), Ok(Instruction::Drw { x: 1, y: 2, n: 5 }));
+        assert_eq!(decode(0xF733), Ok(Instruction::LdBcd(7)));
+    }
+
+    #[test]
+    fn test_decode_unknown() {
+        assert_eq!(decode(0x5121), Err(DecodeError::UnknownOpcode(0x5121)));
+        assert_eq!(decode(0xF100), Err(DecodeError::UnknownOpcode(0xF100)));
+        assert_eq!(decode(0x812F), Err(DecodeError::UnknownOpcode(0x812F)));
+        assert_eq!(decode(0x9121), Err(DecodeError::UnknownOpcode(0x9121)));
+        assert_eq!(decode(0xE19F), Err(DecodeError::UnknownOpcode(0xE19F)));
+        assert_eq!(decode(0xF1FF), Err(DecodeError::UnknownOpcode(0xF1FF)));
+    }
+
+    #[test]
+    fn test_decode_for_instruction_set() {
+        assert_eq!(
+            decode_for(0x00FF, InstructionSet::SuperChip),
+            Ok(Instruction::High)
+        );
+        assert_eq!(
+            decode_for(0x00C4, InstructionSet::SuperChip),
+            Ok(Instruction::Scd(4))
+        );
+        assert_eq!(
+            decode_for(0xF130, InstructionSet::SuperChip),
+            Ok(Instruction::LdHf(1))
+        );
+        // on CHIP-8 these are machine code calls or unknown
+        assert_eq!(
+            decode_for(0x00FF, InstructionSet::Chip8),
+            Ok(Instruction::Sys(0x0FF))
+        );
+        assert_eq!(
+            decode_for(0xF130, InstructionSet::Chip8),
+            Err(DecodeError::UnknownOpcode(0xF130))
+        );
+        assert_eq!(
+            decode_for(0xF000, InstructionSet::XoChip),
+            Ok(Instruction::LdILong)
+        );
+        assert_eq!(
+            decode_for(0x5123, InstructionSet::XoChip),
+            Ok(Instruction::LoadRange { x: 1, y: 2 })
+        );
+        assert_eq!(
+            decode_for(0x00D2, InstructionSet::SuperChip),
+            Ok(Instruction::Sys(0x0D2))
+        );
+        assert_eq!(
+            decode_for(0xF000, InstructionSet::SuperChip),
+            Err(DecodeError::UnknownOpcode(0xF000))
+        );
+        assert_eq!(
+            decode_for(0x0123, InstructionSet::MegaChip),
+            Ok(Instruction::Ldhi(0x23))
+        );
+        assert_eq!(
+            decode_for(0x0123, InstructionSet::XoChip),
+            Ok(Instruction::Sys(0x123))
+        );
+        assert_eq!(
+            decode_for(0x0803, InstructionSet::MegaChip),
+            Ok(Instruction::Bmode(3))
+        );
+        assert_eq!(
+            decode_for(0x0806, InstructionSet::MegaChip),
+            Ok(Instruction::Sys(0x806))
+        );
+        assert_eq!(
+            decode_for(0xF000, InstructionSet::MegaChip),
+            Err(DecodeError::UnknownOpcode(0xF000))
+        );
+    }
+
+    #[test]
+    fn test_decode_for_vip_variants() {
+        assert_eq!(
+            decode_for(0x0230, InstructionSet::TwoPageHires),
+            Ok(Instruction::ClsHires)
+        );
+        assert_eq!(
+            decode_for(0x0230, InstructionSet::Chip8),
+            Ok(Instruction::Sys(0x230))
+        );
+        assert_eq!(
+            decode_for(0x02A0, InstructionSet::Chip8X),
+            Ok(Instruction::Bgcol)
+        );
+        assert_eq!(
+            decode_for(0xB123, InstructionSet::Chip8X),
+            Ok(Instruction::Col { x: 1, y: 2, n: 3 })
+        );
+        assert_eq!(
+            decode_for(0xB123, InstructionSet::Chip8),
+            Ok(Instruction::JpV0(0x123))
+        );
+        assert_eq!(
+            decode_for(0xE1F2, InstructionSet::Chip8X),
+            Ok(Instruction::SkpSecond(1))
+        );
+        assert_eq!(
+            decode_for(0xE1F2, InstructionSet::Chip8),
+            Err(DecodeError::UnknownOpcode(0xE1F2))
+        );
+        for instruction in [
+            Instruction::ClsHires,
+            Instruction::Bgcol,
+            Instruction::Col { x: 1, y: 2, n: 0 },
+        ] {
+            let instruction_set = if instruction == Instruction::ClsHires {
+                InstructionSet::TwoPageHires
+            } else {
+                InstructionSet::Chip8X
+            };
+            assert_eq!(
+                decode_for(encode(&instruction), instruction_set),
+                Ok(instruction)
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for opcode in 0..=u16::MAX {
+            if let Ok(instruction) = decode(opcode) {
+                assert_eq!(encode(&instruction), opcode);
+            }
+        }
+    }
+}
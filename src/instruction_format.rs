@@ -19,8 +19,7 @@ impl InstructionFormat {
         let nibbles_3_to_4 =
             crate::bit_utils::bit_range_to_num(instruction[1].into(), 0, 8).unwrap() as u8;
         let nibbles_2_to_4 =
-            crate::bit_utils::append_number_bits(&[second_nibble as u8, nibbles_3_to_4 as u8])
-                as usize;
+            crate::bit_utils::append_number_bits(&[second_nibble as u8, nibbles_3_to_4]) as usize;
         Self {
             first_nibble,
             second_nibble,
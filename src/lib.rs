@@ -1,9 +1,30 @@
+pub mod assembler;
+pub mod audio;
 pub mod bit_utils;
+pub mod buzzer;
+pub mod chip8x;
+pub mod cli;
+pub mod config;
+pub mod debugger;
+pub mod disassembler;
 pub mod emulator;
+pub mod error;
+pub mod flags;
+pub mod framebuffer;
+pub mod frontend;
+pub mod gdb;
 pub mod instance;
+pub mod instruction;
 pub mod instruction_format;
+pub mod megachip;
+pub mod palette;
+pub mod platform;
+pub mod quirks;
 pub mod renderer;
+pub mod rng;
+pub mod scheduler;
 pub mod screen;
 pub mod stack;
 pub mod timer;
+pub mod tui;
 pub mod vertex;
@@ -1,6 +1,108 @@
 #[tokio::main]
 async fn main() {
-    let file_path = "./roms/test_opcode.ch8";
-    let emulator = chip8::emulator::Emulator::new(Some(file_path), false, false).await;
-    pollster::block_on(emulator.run());
+    let (options, debug, gdb_port) = match chip8::cli::parse(std::env::args().skip(1)) {
+        Ok(chip8::cli::Command::Run(options)) => (options, false, None),
+        Ok(chip8::cli::Command::Debug(options)) => (options, true, None),
+        Ok(chip8::cli::Command::Gdb(options)) => (options.run, false, Some(options.port)),
+        Ok(chip8::cli::Command::Disasm(options)) => {
+            let rom = match std::fs::read(&options.rom) {
+                Ok(rom) => rom,
+                Err(error) => {
+                    let error = chip8::error::Chip8Error::RomUnreadable {
+                        path: options.rom,
+                        reason: error.to_string(),
+                    };
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            };
+            let disassembly = chip8::disassembler::disassemble(
+                &rom,
+                options.platform.memory.start_address,
+                options.platform.instruction_set,
+            );
+            // a closed pipe, as with `chip8 disasm game.ch8 | head`, is not an error
+            let _ = std::io::Write::write_all(
+                &mut std::io::stdout(),
+                disassembly.listing(options.syntax).as_bytes(),
+            );
+            return;
+        }
+        Ok(chip8::cli::Command::Asm(options)) => {
+            let source = match std::fs::read_to_string(&options.source) {
+                Ok(source) => source,
+                Err(error) => {
+                    eprintln!("could not read {}: {}", options.source, error);
+                    std::process::exit(1);
+                }
+            };
+            let program =
+                match chip8::assembler::assemble(&source, options.platform.memory.start_address) {
+                    Ok(program) => program,
+                    Err(error) => {
+                        eprintln!("{}:{}", options.source, error);
+                        std::process::exit(1);
+                    }
+                };
+            let written = std::fs::write(&options.output, &program.rom)
+                .map_err(|error| (&options.output, error))
+                .and_then(|()| match &options.symbols {
+                    Some(path) => {
+                        std::fs::write(path, program.symbol_map()).map_err(|error| (path, error))
+                    }
+                    None => Ok(()),
+                });
+            if let Err((path, error)) = written {
+                eprintln!("could not write {}: {}", path, error);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(chip8::cli::Command::Help) => {
+            println!("{}", chip8::cli::USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, chip8::cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut emulator =
+        match chip8::emulator::Emulator::new(Some(&options.rom), options.config).await {
+            Ok(emulator) => emulator,
+            Err(error) => {
+                eprintln!("{}", error);
+                if let Some(hint) = chip8::cli::hint(&error) {
+                    eprintln!("{}", hint);
+                }
+                std::process::exit(1);
+            }
+        };
+    emulator.set_flag_store(Box::new(match &options.flags_dir {
+        Some(directory) => chip8::flags::FileFlagStore::new(directory),
+        None => chip8::flags::FileFlagStore::default(),
+    }));
+    if let Some(port) = gdb_port {
+        let served = std::net::TcpListener::bind(("127.0.0.1", port)).and_then(|listener| {
+            eprintln!("waiting for gdb on 127.0.0.1:{}", port);
+            chip8::gdb::run(chip8::debugger::Debugger::new(emulator), listener)
+        });
+        if let Err(error) = served {
+            eprintln!("gdb server failed: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if debug {
+        if let Err(error) = chip8::tui::run(chip8::debugger::Debugger::new(emulator)) {
+            eprintln!("debugger failed: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    emulator.set_audio_sink(chip8::audio::output_or_null(
+        !options.mute,
+        chip8::buzzer::BuzzerSettings::default(),
+    ));
+    pollster::block_on(chip8::frontend::run(emulator, options.frontend));
 }
@@ -0,0 +1,159 @@
+/// MEGA-CHIP addresses 24 bits of memory.
+pub const MEMORY_SIZE: usize = 1 << 24;
+/// The palette index that 1-bit font sprites are drawn with in MEGA-CHIP mode.
+pub const FONT_COLOR: u8 = 0xFF;
+
+/// How 080N combines color sprites with what is already on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The sprite replaces the screen.
+    Normal,
+    /// The sprite is drawn at 25% opacity.
+    Alpha25,
+    /// The sprite is drawn at 50% opacity.
+    Alpha50,
+    /// The sprite is drawn at 75% opacity.
+    Alpha75,
+    /// The sprite's color is added to the screen's.
+    Add,
+    /// The sprite's color is multiplied with the screen's.
+    Multiply,
+}
+
+impl BlendMode {
+    /// The blend mode selected by 080N, if N is one.
+    pub fn from_nibble(n: u8) -> Option<Self> {
+        match n {
+            0 => Some(BlendMode::Normal),
+            1 => Some(BlendMode::Alpha25),
+            2 => Some(BlendMode::Alpha50),
+            3 => Some(BlendMode::Alpha75),
+            4 => Some(BlendMode::Add),
+            5 => Some(BlendMode::Multiply),
+            _ => None,
+        }
+    }
+
+    /// Combines a sprite's color with the color on the screen.
+    pub fn blend(&self, screen: [u8; 3], sprite: [u8; 3]) -> [u8; 3] {
+        let mix = |opacity: u16| {
+            std::array::from_fn(|i| {
+                ((sprite[i] as u16 * opacity + screen[i] as u16 * (4 - opacity)) / 4) as u8
+            })
+        };
+        match self {
+            BlendMode::Normal => sprite,
+            BlendMode::Alpha25 => mix(1),
+            BlendMode::Alpha50 => mix(2),
+            BlendMode::Alpha75 => mix(3),
+            BlendMode::Add => std::array::from_fn(|i| screen[i].saturating_add(sprite[i])),
+            BlendMode::Multiply => {
+                std::array::from_fn(|i| (screen[i] as u16 * sprite[i] as u16 / 255) as u8)
+            }
+        }
+    }
+}
+
+/// A digitized sound played by 060N: unsigned 8-bit mono samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub sample_rate: u16,
+    pub data: Vec<u8>,
+    /// Whether the sound repeats until it is stopped by 0700.
+    pub looping: bool,
+}
+
+impl Sample {
+    /// The length of the header in front of the samples: a 16-bit sample rate, a 24-bit length and a zero byte.
+    pub const HEADER_LENGTH: usize = 6;
+
+    /// Reads the sound at the start of `memory`. Returns None if the samples run past the end of memory.
+    pub fn parse(memory: &[u8], looping: bool) -> Option<Self> {
+        let header = memory.get(..Self::HEADER_LENGTH)?;
+        let sample_rate = u16::from_be_bytes([header[0], header[1]]);
+        let length = u32::from_be_bytes([0, header[2], header[3], header[4]]) as usize;
+        let data = memory
+            .get(Self::HEADER_LENGTH..Self::HEADER_LENGTH + length)?
+            .to_vec();
+        Some(Self {
+            sample_rate,
+            data,
+            looping,
+        })
+    }
+}
+
+/// The registers MEGA-CHIP adds to the interpreter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MegaChipState {
+    /// Whether 0011 switched to the 256x192 color mode.
+    pub enabled: bool,
+    /// 256 ARGB colors. Index 0 is transparent in sprites.
+    pub palette: Vec<[u8; 4]>,
+    pub sprite_width: usize,
+    pub sprite_height: usize,
+    pub blend_mode: BlendMode,
+    /// Drawing over a pixel of this palette index sets VF.
+    pub collision_color: u8,
+}
+
+impl MegaChipState {
+    pub fn new() -> Self {
+        let mut palette = vec![[0xFF; 4]; 256];
+        palette[0] = [0; 4];
+        Self {
+            enabled: false,
+            palette,
+            sprite_width: 256,
+            sprite_height: 256,
+            blend_mode: BlendMode::Normal,
+            collision_color: 0,
+        }
+    }
+
+    /// The RGB part of a palette entry.
+    pub fn rgb(&self, index: u8) -> [u8; 3] {
+        let [_, red, green, blue] = self.palette[index as usize];
+        [red, green, blue]
+    }
+}
+
+impl Default for MegaChipState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod megachip_tests {
+    use super::{BlendMode, Sample};
+
+    #[test]
+    fn test_blend() {
+        let screen = [200, 100, 0];
+        let sprite = [0, 100, 200];
+        assert_eq!(BlendMode::Normal.blend(screen, sprite), sprite);
+        assert_eq!(BlendMode::Alpha50.blend(screen, sprite), [100, 100, 100]);
+        assert_eq!(BlendMode::Alpha25.blend(screen, sprite), [150, 100, 50]);
+        assert_eq!(BlendMode::Add.blend(screen, sprite), [200, 200, 200]);
+        assert_eq!(
+            BlendMode::Multiply.blend([255, 128, 0], [128, 255, 255]),
+            [128, 128, 0]
+        );
+        assert_eq!(BlendMode::from_nibble(6), None);
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let memory = [0x1F, 0x40, 0x00, 0x00, 0x03, 0x00, 1, 2, 3, 4];
+        assert_eq!(
+            Sample::parse(&memory, false),
+            Some(Sample {
+                sample_rate: 8000,
+                data: vec![1, 2, 3],
+                looping: false
+            })
+        );
+        assert_eq!(Sample::parse(&memory[..8], false), None);
+    }
+}
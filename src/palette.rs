@@ -0,0 +1,43 @@
+/// The colors the renderer shows for each combination of lit planes, as RGB bytes.
+/// Index 0 is the background, 1 the first plane, 2 the second plane and 3 both planes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [[u8; 3]; 4],
+}
+
+impl Palette {
+    pub fn new(colors: [[u8; 3]; 4]) -> Self {
+        Self { colors }
+    }
+
+    /// The color of a pixel with the given planes lit, with channels from 0.0 to 1.0.
+    pub fn color(&self, planes: u8) -> [f32; 3] {
+        self.colors[(planes & crate::framebuffer::ALL_PLANES) as usize]
+            .map(|channel| channel as f32 / u8::MAX as f32)
+    }
+}
+
+/// White on black, with greys for the second plane so that monochrome ROMs look as they always have.
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new([
+            [0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF],
+            [0xAA, 0xAA, 0xAA],
+            [0x55, 0x55, 0x55],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod palette_tests {
+    use super::Palette;
+
+    #[test]
+    fn test_color() {
+        let palette = Palette::new([[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]]);
+        assert_eq!(palette.color(0), [0.0, 0.0, 0.0]);
+        assert_eq!(palette.color(1), [1.0, 0.0, 0.0]);
+        assert_eq!(palette.color(3), [0.0, 0.0, 1.0]);
+    }
+}
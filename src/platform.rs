@@ -0,0 +1,312 @@
+/// The font from the COSMAC VIP's interpreter.
+pub const COSMAC_VIP_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+/// The ETI-660's 3 pixel wide font.
+pub const ETI_660_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x20, 0x20, 0x20, 0x20, 0x20, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+/// The DREAM 6800's 3 pixel wide font.
+pub const DREAM_6800_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+/// The font from CHIP-48, which SUPER-CHIP and Octo kept.
+pub const CHIP_48_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+/// The SUPER-CHIP 1.1 big font. Its A to F come from Octo.
+pub const SCHIP_BIG_FONT: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/// What memory holds before the fonts and the ROM are loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitialMemory {
+    Zero,
+    /// Bytes from the emulator's seed, like the uninitialized RAM of real hardware.
+    /// Runs with the same seed start with the same memory.
+    Random,
+}
+
+/// Where a platform keeps the program and the fonts, and how much memory it has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLayout {
+    /// Where the ROM is loaded and execution starts.
+    pub start_address: usize,
+    /// Where the font is stored. The big font, if any, follows it.
+    pub font_address: usize,
+    pub font: &'static [u8; 80],
+    pub big_font: Option<&'static [u8; 160]>,
+    pub memory_size: usize,
+    pub initial_memory: InitialMemory,
+}
+
+impl MemoryLayout {
+    /// Where the 10-byte big font characters start.
+    pub fn big_font_address(&self) -> usize {
+        self.font_address + self.font.len()
+    }
+}
+
+/// The CHIP-48 font at 0x50 followed by the big font, with programs at 0x200 in 4 KiB of zeroed memory.
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        Self {
+            start_address: 0x200,
+            font_address: 0x50,
+            font: &CHIP_48_FONT,
+            big_font: Some(&SCHIP_BIG_FONT),
+            memory_size: 4096,
+            initial_memory: InitialMemory::Zero,
+        }
+    }
+}
+
+/// A machine that ran CHIP-8: its instructions, quirks, speed and memory layout.
+/// Selecting a platform configures the whole emulator, see `EmulatorConfig::for_platform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    /// The short name used to pick the platform, e.g. on the command line.
+    pub name: &'static str,
+    pub instruction_set: crate::instruction::InstructionSet,
+    pub quirks: crate::quirks::Quirks,
+    pub instructions_per_frame: usize,
+    pub stack_depth: usize,
+    pub memory: MemoryLayout,
+}
+
+impl Platform {
+    /// The original interpreter on the RCA COSMAC VIP.
+    pub const COSMAC_VIP: Platform = Platform {
+        name: "vip",
+        instruction_set: crate::instruction::InstructionSet::Chip8,
+        quirks: crate::quirks::Quirks::COSMAC_VIP,
+        instructions_per_frame: 15,
+        stack_depth: crate::stack::COSMAC_VIP_DEPTH,
+        memory: MemoryLayout {
+            start_address: 0x200,
+            font_address: 0x50,
+            font: &COSMAC_VIP_FONT,
+            big_font: None,
+            memory_size: 4096,
+            initial_memory: InitialMemory::Zero,
+        },
+    };
+
+    /// The ETI-660 learning computer, whose programs start at 0x600. Its 64x48 display is not emulated.
+    pub const ETI_660: Platform = Platform {
+        name: "eti660",
+        memory: MemoryLayout {
+            start_address: 0x600,
+            font: &ETI_660_FONT,
+            ..Self::COSMAC_VIP.memory
+        },
+        ..Self::COSMAC_VIP
+    };
+
+    /// The DREAM 6800's CHIPOS interpreter.
+    pub const DREAM_6800: Platform = Platform {
+        name: "dream6800",
+        memory: MemoryLayout {
+            font: &DREAM_6800_FONT,
+            ..Self::COSMAC_VIP.memory
+        },
+        ..Self::COSMAC_VIP
+    };
+
+    /// The COSMAC VIP's two-page interpreter with a 64x64 display.
+    pub const TWO_PAGE_HIRES: Platform = Platform {
+        name: "vip-hires",
+        instruction_set: crate::instruction::InstructionSet::TwoPageHires,
+        ..Self::COSMAC_VIP
+    };
+
+    /// The COSMAC VIP with the CHIP-8X interpreter, the VP-590 color board and a second keypad.
+    pub const CHIP_8X: Platform = Platform {
+        name: "chip8x",
+        instruction_set: crate::instruction::InstructionSet::Chip8X,
+        ..Self::COSMAC_VIP
+    };
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub const CHIP_48: Platform = Platform {
+        name: "chip48",
+        instruction_set: crate::instruction::InstructionSet::Chip8,
+        quirks: crate::quirks::Quirks::CHIP_48,
+        instructions_per_frame: 30,
+        stack_depth: crate::stack::SCHIP_DEPTH,
+        memory: MemoryLayout {
+            start_address: 0x200,
+            font_address: 0x50,
+            font: &CHIP_48_FONT,
+            big_font: None,
+            memory_size: 4096,
+            initial_memory: InitialMemory::Zero,
+        },
+    };
+
+    /// SUPER-CHIP 1.1 on the HP-48 calculators.
+    pub const SCHIP: Platform = Platform {
+        name: "schip",
+        instruction_set: crate::instruction::InstructionSet::SuperChip,
+        quirks: crate::quirks::Quirks::SCHIP_1_1,
+        memory: MemoryLayout {
+            big_font: Some(&SCHIP_BIG_FONT),
+            ..Self::CHIP_48.memory
+        },
+        ..Self::CHIP_48
+    };
+
+    /// XO-CHIP as defined by Octo.
+    pub const XO_CHIP: Platform = Platform {
+        name: "xochip",
+        instruction_set: crate::instruction::InstructionSet::XoChip,
+        quirks: crate::quirks::Quirks::XO_CHIP,
+        instructions_per_frame: 1000,
+        stack_depth: crate::stack::XO_CHIP_DEPTH,
+        memory: MemoryLayout {
+            memory_size: crate::instruction::InstructionSet::XoChip.memory_size(),
+            ..Self::SCHIP.memory
+        },
+    };
+
+    /// MEGA-CHIP as implemented by Mega8.
+    pub const MEGA_CHIP: Platform = Platform {
+        name: "megachip",
+        instruction_set: crate::instruction::InstructionSet::MegaChip,
+        quirks: crate::quirks::Quirks::SCHIP_MODERN,
+        instructions_per_frame: 1000,
+        memory: MemoryLayout {
+            memory_size: crate::instruction::InstructionSet::MegaChip.memory_size(),
+            ..Self::SCHIP.memory
+        },
+        ..Self::SCHIP
+    };
+
+    /// Every preset, in roughly historical order.
+    pub const ALL: [Platform; 9] = [
+        Self::COSMAC_VIP,
+        Self::TWO_PAGE_HIRES,
+        Self::CHIP_8X,
+        Self::ETI_660,
+        Self::DREAM_6800,
+        Self::CHIP_48,
+        Self::SCHIP,
+        Self::XO_CHIP,
+        Self::MEGA_CHIP,
+    ];
+
+    /// The preset with the given name.
+    pub fn from_name(name: &str) -> Option<Platform> {
+        Self::ALL
+            .into_iter()
+            .find(|platform| platform.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod platform_tests {
+    use super::Platform;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Platform::from_name("ETI660"), Some(Platform::ETI_660));
+        assert_eq!(Platform::from_name("xochip"), Some(Platform::XO_CHIP));
+        assert_eq!(Platform::from_name("gameboy"), None);
+    }
+
+    #[test]
+    fn test_layouts_fit() {
+        for platform in Platform::ALL {
+            let memory = platform.memory;
+            let fonts_end =
+                memory.big_font_address() + memory.big_font.map_or(0, |font| font.len());
+            assert!(fonts_end <= memory.start_address, "{}", platform.name);
+            assert!(
+                memory.start_address < memory.memory_size,
+                "{}",
+                platform.name
+            );
+            assert!(
+                memory.memory_size >= platform.instruction_set.memory_size(),
+                "{}",
+                platform.name
+            );
+        }
+    }
+}
@@ -0,0 +1,125 @@
+/// How FX55 and FX65 change the index register after copying registers to or from memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryIncrement {
+    /// The index register is left untouched.
+    None,
+    /// The index register is incremented by X (CHIP-48 and SCHIP 1.0).
+    X,
+    /// The index register is incremented by X + 1 (COSMAC VIP and XO-CHIP).
+    XPlusOne,
+}
+
+/// The behaviors that differ between CHIP-8 interpreters. Each switch can be chosen independently,
+/// but most ROMs are happy with one of the named presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8XY1, 8XY2 and 8XY3 reset register F to 0.
+    pub vf_reset: bool,
+    /// How FX55 and FX65 change the index register.
+    pub memory_increment: MemoryIncrement,
+    /// 8XY6 and 8XYE shift register Y into register X instead of shifting register X in place.
+    pub shift_uses_vy: bool,
+    /// BNNN jumps to XNN + VX instead of NNN + V0.
+    pub jump_with_vx: bool,
+    /// Sprites are clipped at the edges of the screen instead of wrapping around.
+    pub clip_sprites: bool,
+    /// DXYN waits for the next frame before drawing, so at most one sprite is drawn per frame.
+    pub display_wait: bool,
+    /// FX0A finishes when the key is released instead of when it is pressed.
+    pub key_on_release: bool,
+    /// In high resolution, DXYN sets VF to the number of sprite rows that collided or were clipped
+    /// at the bottom of the screen instead of to 1 (SUPER-CHIP 1.0 and 1.1).
+    pub count_collided_rows: bool,
+}
+
+impl Quirks {
+    /// The original interpreter on the COSMAC VIP.
+    pub const COSMAC_VIP: Quirks = Quirks {
+        vf_reset: true,
+        memory_increment: MemoryIncrement::XPlusOne,
+        shift_uses_vy: true,
+        jump_with_vx: false,
+        clip_sprites: true,
+        display_wait: true,
+        key_on_release: true,
+        count_collided_rows: false,
+    };
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub const CHIP_48: Quirks = Quirks {
+        vf_reset: false,
+        memory_increment: MemoryIncrement::X,
+        shift_uses_vy: false,
+        jump_with_vx: true,
+        clip_sprites: true,
+        display_wait: false,
+        key_on_release: false,
+        count_collided_rows: false,
+    };
+
+    /// SUPER-CHIP 1.0.
+    pub const SCHIP_1_0: Quirks = Quirks {
+        vf_reset: false,
+        memory_increment: MemoryIncrement::X,
+        shift_uses_vy: false,
+        jump_with_vx: true,
+        clip_sprites: true,
+        display_wait: true,
+        key_on_release: false,
+        count_collided_rows: true,
+    };
+
+    /// SUPER-CHIP 1.1.
+    pub const SCHIP_1_1: Quirks = Quirks {
+        vf_reset: false,
+        memory_increment: MemoryIncrement::None,
+        shift_uses_vy: false,
+        jump_with_vx: true,
+        clip_sprites: true,
+        display_wait: true,
+        key_on_release: false,
+        count_collided_rows: true,
+    };
+
+    /// SUPER-CHIP as implemented by modern interpreters such as Octo, without the display wait.
+    pub const SCHIP_MODERN: Quirks = Quirks {
+        vf_reset: false,
+        memory_increment: MemoryIncrement::None,
+        shift_uses_vy: false,
+        jump_with_vx: true,
+        clip_sprites: true,
+        display_wait: false,
+        key_on_release: false,
+        count_collided_rows: false,
+    };
+
+    /// XO-CHIP as defined by Octo.
+    pub const XO_CHIP: Quirks = Quirks {
+        vf_reset: false,
+        memory_increment: MemoryIncrement::XPlusOne,
+        shift_uses_vy: true,
+        jump_with_vx: false,
+        clip_sprites: false,
+        display_wait: false,
+        key_on_release: false,
+        count_collided_rows: false,
+    };
+
+    /// The named presets, as they are spelled on the command line.
+    pub const PRESETS: [(&'static str, Quirks); 6] = [
+        ("vip", Self::COSMAC_VIP),
+        ("chip48", Self::CHIP_48),
+        ("schip1.0", Self::SCHIP_1_0),
+        ("schip1.1", Self::SCHIP_1_1),
+        ("schip-modern", Self::SCHIP_MODERN),
+        ("xochip", Self::XO_CHIP),
+    ];
+
+    /// The preset with the given name.
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        Self::PRESETS
+            .into_iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, quirks)| quirks)
+    }
+}
@@ -14,10 +14,13 @@ pub struct RendererState {
     num_indices: u32,
     pub instances: Vec<crate::instance::Instance>,
     instance_buffer: wgpu::Buffer,
+    /// The resolution the pixel grid was built for.
+    grid_size: (usize, usize),
+    palette: crate::palette::Palette,
 }
 
 impl RendererState {
-    pub async fn new(window: winit::window::Window) -> Self {
+    pub async fn new(window: winit::window::Window, palette: crate::palette::Palette) -> Self {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -121,6 +124,10 @@ impl RendererState {
         });
 
         let unit_pixel = crate::screen::Pixel::new();
+        let grid_size = (
+            crate::screen::SCREEN_WIDTH as usize,
+            crate::screen::SCREEN_HEIGHT as usize,
+        );
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
@@ -136,40 +143,7 @@ impl RendererState {
 
         let num_indices = unit_pixel.indices.len() as u32;
 
-        // instances are 0-indexed from the bottom-left going top-right
-        let instances = (0..crate::screen::SCREEN_HEIGHT)
-            .flat_map(|y| {
-                (0..crate::screen::SCREEN_WIDTH).map(move |x| {
-                    let position = cgmath::Vector3 {
-                        x: crate::screen::PIXEL_WIDTH * x as f32,
-                        y: crate::screen::PIXEL_HEIGHT * y as f32,
-                        z: 0.0,
-                    };
-
-                    let color = cgmath::Vector4 {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 0.0,
-                        w: 0.0,
-                    };
-
-                    let rotation = if position.is_zero() {
-                        cgmath::Quaternion::from_axis_angle(
-                            cgmath::Vector3::unit_z(),
-                            cgmath::Deg(0.0),
-                        )
-                    } else {
-                        cgmath::Quaternion::from_axis_angle(position.normalize(), cgmath::Deg(0.0))
-                    };
-
-                    crate::instance::Instance {
-                        position,
-                        rotation,
-                        color,
-                    }
-                })
-            })
-            .collect::<Vec<_>>();
+        let instances = grid_instances(crate::screen::SCREEN_WIDTH, crate::screen::SCREEN_HEIGHT);
 
         let instance_data = instances
             .iter()
@@ -194,6 +168,8 @@ impl RendererState {
             num_indices,
             instances,
             instance_buffer,
+            grid_size,
+            palette,
         }
     }
 
@@ -214,7 +190,48 @@ impl RendererState {
         false
     }
 
-    pub fn update(&mut self) {
+    /// Colors each instance from the emulator's framebuffer and uploads the instance data.
+    pub fn update(&mut self, framebuffer: &crate::framebuffer::Framebuffer) {
+        // the emulator switched resolutions, so the grid of pixels is rebuilt to match
+        if self.grid_size != (framebuffer.width(), framebuffer.height()) {
+            self.grid_size = (framebuffer.width(), framebuffer.height());
+            let width = framebuffer.width() as u32;
+            let height = framebuffer.height() as u32;
+            let unit_pixel = crate::screen::Pixel::for_grid(width, height);
+            self.vertex_buffer =
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&unit_pixel.vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    });
+            self.instances = grid_instances(width, height);
+        }
+
+        for y in 0..framebuffer.height() {
+            for x in 0..framebuffer.width() {
+                // instances are indexed from the bottom-left, so we flip vertically
+                let instance_index = (framebuffer.height() - 1 - y) * framebuffer.width() + x;
+                // MEGA-CHIP pixels carry their own color and CHIP-8X colors by zone,
+                // the rest are looked up in the palette
+                let rgb = match (framebuffer.color(x, y), framebuffer.color_zones()) {
+                    (Some(rgb), _) => Some(rgb),
+                    (None, Some(zones)) => Some(zones.color(x, y, framebuffer.get(x, y))),
+                    (None, None) => None,
+                };
+                let [red, green, blue] = match rgb {
+                    Some(rgb) => rgb.map(|channel| channel as f32 / u8::MAX as f32),
+                    None => self.palette.color(framebuffer.planes(x, y)),
+                };
+                self.instances[instance_index].color = cgmath::Vector4 {
+                    x: red,
+                    y: green,
+                    z: blue,
+                    w: 0.0,
+                };
+            }
+        }
+
         let instance_data = self
             .instances
             .iter()
@@ -271,3 +288,40 @@ impl RendererState {
         Ok(())
     }
 }
+
+/// Builds one instance per pixel of a `width` x `height` grid covering the window.
+/// Instances are 0-indexed from the bottom-left going top-right.
+fn grid_instances(width: u32, height: u32) -> Vec<crate::instance::Instance> {
+    let pixel_width = 2.0 / width as f32;
+    let pixel_height = 2.0 / height as f32;
+    (0..height)
+        .flat_map(|y| {
+            (0..width).map(move |x| {
+                let position = cgmath::Vector3 {
+                    x: pixel_width * x as f32,
+                    y: pixel_height * y as f32,
+                    z: 0.0,
+                };
+
+                let color = cgmath::Vector4 {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    w: 0.0,
+                };
+
+                let rotation = if position.is_zero() {
+                    cgmath::Quaternion::from_axis_angle(cgmath::Vector3::unit_z(), cgmath::Deg(0.0))
+                } else {
+                    cgmath::Quaternion::from_axis_angle(position.normalize(), cgmath::Deg(0.0))
+                };
+
+                crate::instance::Instance {
+                    position,
+                    rotation,
+                    color,
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+}
@@ -0,0 +1,82 @@
+/// Where CXNN gets its random bytes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomMode {
+    /// A seeded xorshift generator. The same seed always yields the same sequence, on every platform and build.
+    Xorshift,
+    /// Mimics the COSMAC VIP interpreter's routine: a 16-bit seed is incremented, its low byte indexes into
+    /// page 1 of memory (where the VIP kept its interpreter), and the byte found there is added to the high byte.
+    /// Like on the VIP, the output depends on what is stored in memory below 0x200.
+    CosmacVip,
+}
+
+/// The random number source behind CXNN. It is plain data so it can be captured in save states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    mode: RandomMode,
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(mode: RandomMode, seed: u64) -> Self {
+        let state = match mode {
+            // xorshift gets stuck on 0
+            RandomMode::Xorshift if seed == 0 => 0x9E37_79B9_7F4A_7C15,
+            RandomMode::Xorshift => seed,
+            RandomMode::CosmacVip => seed & 0xFFFF,
+        };
+        Self { mode, state }
+    }
+
+    pub fn mode(&self) -> RandomMode {
+        self.mode
+    }
+
+    /// Returns the next random byte. `memory` is only read in COSMAC VIP mode.
+    pub fn next_byte(&mut self, memory: &[u8]) -> u8 {
+        match self.mode {
+            RandomMode::Xorshift => {
+                // xorshift64*
+                self.state ^= self.state >> 12;
+                self.state ^= self.state << 25;
+                self.state ^= self.state >> 27;
+                (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+            }
+            RandomMode::CosmacVip => {
+                let [high, low] = (self.state as u16).wrapping_add(1).to_be_bytes();
+                let high = high.wrapping_add(memory[0x100 + low as usize]);
+                self.state = u16::from_be_bytes([high, low]) as u64;
+                high
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod rng_tests {
+    use super::{RandomMode, Rng};
+
+    #[test]
+    fn test_xorshift_is_deterministic() {
+        let memory = [0; 4096];
+        let mut left = Rng::new(RandomMode::Xorshift, 1234);
+        let mut right = Rng::new(RandomMode::Xorshift, 1234);
+        let left_bytes: Vec<u8> = (0..32).map(|_| left.next_byte(&memory)).collect();
+        let right_bytes: Vec<u8> = (0..32).map(|_| right.next_byte(&memory)).collect();
+        assert_eq!(left_bytes, right_bytes);
+        assert!(left_bytes.iter().any(|byte| *byte != left_bytes[0]));
+
+        let mut other = Rng::new(RandomMode::Xorshift, 4321);
+        let other_bytes: Vec<u8> = (0..32).map(|_| other.next_byte(&memory)).collect();
+        assert_ne!(left_bytes, other_bytes);
+    }
+
+    #[test]
+    fn test_cosmac_vip_reads_interpreter_page() {
+        let mut memory = [0; 4096];
+        memory[0x101] = 0x10;
+        memory[0x102] = 0x05;
+        let mut rng = Rng::new(RandomMode::CosmacVip, 0x2000);
+        assert_eq!(rng.next_byte(&memory), 0x30);
+        assert_eq!(rng.next_byte(&memory), 0x35);
+    }
+}
@@ -0,0 +1,110 @@
+/// The delay and sound timers always tick at 60 Hz, and one frame of instructions runs per tick.
+pub const TIMER_HZ: u32 = 60;
+/// If the emulator falls further behind than this many frames (e.g. the window was dragged), the backlog is dropped
+/// instead of running every missed frame back to back.
+const MAX_FRAMES_PER_ADVANCE: u32 = 15;
+
+/// How closely the scheduler has kept to real time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DriftStats {
+    /// Total wall-clock time fed into the scheduler.
+    pub elapsed: std::time::Duration,
+    /// Frames that were run.
+    pub frames_run: u64,
+    /// Frames that were skipped because the emulator fell too far behind.
+    pub frames_dropped: u64,
+    /// The largest backlog seen at the start of an advance.
+    pub max_lag: std::time::Duration,
+}
+
+impl DriftStats {
+    /// How far emulated time trails wall-clock time, in seconds. Dropped frames count as drift.
+    pub fn drift_seconds(&self, frame_length: std::time::Duration) -> f64 {
+        self.elapsed.as_secs_f64() - frame_length.as_secs_f64() * self.frames_run as f64
+    }
+}
+
+/// A fixed-timestep scheduler. Wall-clock time goes into an accumulator and whole frames come out,
+/// so the timers tick at exactly 60 Hz no matter how often the event loop wakes us.
+pub struct Scheduler {
+    frame_length: std::time::Duration,
+    accumulator: std::time::Duration,
+    stats: DriftStats,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            frame_length: std::time::Duration::from_secs(1) / TIMER_HZ,
+            accumulator: std::time::Duration::ZERO,
+            stats: DriftStats::default(),
+        }
+    }
+
+    pub fn frame_length(&self) -> std::time::Duration {
+        self.frame_length
+    }
+
+    pub fn stats(&self) -> &DriftStats {
+        &self.stats
+    }
+
+    /// Adds the wall-clock time since the last advance and returns how many frames should be run now.
+    pub fn advance(&mut self, elapsed: std::time::Duration) -> u32 {
+        self.stats.elapsed += elapsed;
+        self.accumulator += elapsed;
+        self.stats.max_lag = self.stats.max_lag.max(self.accumulator);
+
+        let mut frames = 0;
+        while self.accumulator >= self.frame_length {
+            self.accumulator -= self.frame_length;
+            frames += 1;
+        }
+
+        if frames > MAX_FRAMES_PER_ADVANCE {
+            self.stats.frames_dropped += (frames - MAX_FRAMES_PER_ADVANCE) as u64;
+            frames = MAX_FRAMES_PER_ADVANCE;
+        }
+        self.stats.frames_run += frames as u64;
+        frames
+    }
+
+    /// How long until the next frame is due.
+    pub fn time_until_next_frame(&self) -> std::time::Duration {
+        self.frame_length - self.accumulator
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod scheduler_tests {
+    use super::Scheduler;
+    use std::time::Duration;
+
+    #[test]
+    fn test_advance() {
+        let mut scheduler = Scheduler::new();
+        let frame_length = scheduler.frame_length();
+        assert_eq!(scheduler.advance(frame_length / 2), 0);
+        assert_eq!(scheduler.advance(frame_length / 2), 1);
+        assert_eq!(scheduler.advance(frame_length * 3), 3);
+        assert_eq!(scheduler.time_until_next_frame(), frame_length);
+        assert_eq!(scheduler.stats().frames_run, 4);
+        assert!(scheduler.stats().drift_seconds(frame_length).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_advance_drops_backlog() {
+        let mut scheduler = Scheduler::new();
+        let frame_length = scheduler.frame_length();
+        assert_eq!(scheduler.advance(Duration::from_secs(1)), 15);
+        assert_eq!(scheduler.stats().frames_dropped, 45);
+        assert!(scheduler.stats().drift_seconds(frame_length) > 0.7);
+        assert_eq!(scheduler.stats().max_lag, Duration::from_secs(1));
+    }
+}
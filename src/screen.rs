@@ -1,5 +1,13 @@
 pub const SCREEN_WIDTH: u32 = 64;
 pub const SCREEN_HEIGHT: u32 = 32;
+/// The SUPER-CHIP high resolution mode.
+pub const HIRES_SCREEN_WIDTH: u32 = 128;
+pub const HIRES_SCREEN_HEIGHT: u32 = 64;
+/// The COSMAC VIP two-page hi-res mode is as wide as the regular one, but twice as high.
+pub const TWO_PAGE_SCREEN_HEIGHT: u32 = 64;
+/// The MEGA-CHIP color mode.
+pub const MEGA_SCREEN_WIDTH: u32 = 256;
+pub const MEGA_SCREEN_HEIGHT: u32 = 192;
 pub const PIXEL_WIDTH: f32 = 2.0 / SCREEN_WIDTH as f32;
 pub const PIXEL_HEIGHT: f32 = 2.0 / SCREEN_HEIGHT as f32;
 
@@ -11,6 +19,13 @@ pub struct Pixel {
 // Setting all colors to 0.0 shows a black screen but we've made a bunch of pixels
 impl Pixel {
     pub fn new() -> Self {
+        Self::for_grid(SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+
+    /// A pixel sized to fill one cell of a `width` x `height` grid covering the window.
+    pub fn for_grid(width: u32, height: u32) -> Self {
+        let pixel_width = 2.0 / width as f32;
+        let pixel_height = 2.0 / height as f32;
         // we only want the rectangle to take up a portion of its cell to show a grid
         // remove when no longer debugging, because this causes weird lines on resize
         let grid_multiplier = 0.95;
@@ -18,22 +33,28 @@ impl Pixel {
             vertices: [
                 crate::vertex::Vertex {
                     position: [
-                        -1.0 + PIXEL_WIDTH * grid_multiplier,
-                        -1.0 + PIXEL_HEIGHT * grid_multiplier,
+                        -1.0 + pixel_width * grid_multiplier,
+                        -1.0 + pixel_height * grid_multiplier,
                         0.0,
                     ],
                 },
                 crate::vertex::Vertex {
-                    position: [-1.0, -1.0 + PIXEL_HEIGHT * grid_multiplier, 0.0],
+                    position: [-1.0, -1.0 + pixel_height * grid_multiplier, 0.0],
                 },
                 crate::vertex::Vertex {
                     position: [-1.0, -1.0, 0.0],
                 },
                 crate::vertex::Vertex {
-                    position: [-1.0 + PIXEL_WIDTH * grid_multiplier, -1.0, 0.0],
+                    position: [-1.0 + pixel_width * grid_multiplier, -1.0, 0.0],
                 },
             ],
             indices: [0, 1, 2, 0, 2, 3],
         }
     }
 }
+
+impl Default for Pixel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,21 +1,89 @@
+/// The COSMAC VIP interpreter reserved room for 12 return addresses.
+pub const COSMAC_VIP_DEPTH: usize = 12;
+/// SUPER-CHIP allows 16 levels of nesting.
+pub const SCHIP_DEPTH: usize = 16;
+/// XO-CHIP interpreters such as Octo allow much deeper nesting.
+pub const XO_CHIP_DEPTH: usize = 256;
+
+/// Why a push or pop failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    Overflow,
+    Underflow,
+}
+
+/// The call stack of return addresses, bounded like it is on real hardware.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Stack {
     contents: Vec<usize>,
+    max_depth: usize,
 }
 
 impl Stack {
-    pub fn new() -> Self {
-        Self { contents: vec![] }
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            contents: Vec::with_capacity(max_depth),
+            max_depth,
+        }
     }
 
     pub fn top(&self) -> Option<&usize> {
         self.contents.last()
     }
 
-    pub fn push(&mut self, val: usize) {
+    pub fn push(&mut self, val: usize) -> Result<(), StackError> {
+        if self.contents.len() >= self.max_depth {
+            return Err(StackError::Overflow);
+        }
         self.contents.push(val);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<usize, StackError> {
+        self.contents.pop().ok_or(StackError::Underflow)
+    }
+
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
     }
 
-    pub fn pop(&mut self) {
-        self.contents.pop();
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Iterates over the return addresses from the most recent call to the oldest.
+    pub fn iter(&self) -> std::iter::Rev<std::slice::Iter<'_, usize>> {
+        self.contents.iter().rev()
+    }
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new(SCHIP_DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod stack_tests {
+    use super::{Stack, StackError};
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut stack = Stack::new(2);
+        assert_eq!(stack.push(0x200), Ok(()));
+        assert_eq!(stack.push(0x300), Ok(()));
+        assert_eq!(stack.push(0x400), Err(StackError::Overflow));
+        assert_eq!(
+            stack.iter().copied().collect::<Vec<_>>(),
+            vec![0x300, 0x200]
+        );
+        assert_eq!(stack.pop(), Ok(0x300));
+        assert_eq!(stack.pop(), Ok(0x200));
+        assert_eq!(stack.pop(), Err(StackError::Underflow));
+        assert!(stack.is_empty());
     }
 }
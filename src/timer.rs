@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Timer {
     pub counter: u8,
 }
@@ -9,7 +10,13 @@ impl Timer {
 
     pub fn decrement(&mut self) {
         if self.counter > 0 {
-            self.counter = self.counter - 1;
+            self.counter -= 1;
         }
     }
 }
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
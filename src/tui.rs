@@ -0,0 +1,576 @@
+/// How long the terminal waits for a key before running the next frame.
+const FRAME_DURATION: std::time::Duration = std::time::Duration::from_micros(16_667);
+/// How many instructions are shown before the program counter.
+const DISASSEMBLY_BEFORE: usize = 6;
+const DISASSEMBLY_LINES: usize = 18;
+const DISASSEMBLY_WIDTH: usize = 44;
+const MEMORY_ROWS: usize = 8;
+const MEMORY_ROW_LENGTH: usize = 16;
+/// How many instructions step over and each continued frame may run before giving control back.
+const STEP_OVER_LIMIT: u64 = 1_000_000;
+const HELP: &str =
+    "s step  n step over  c continue/pause  up/down select  b breakpoint  m poke  g memory  k key  q quit";
+
+/// What the line at the bottom of the screen is asking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    /// An address and the bytes to write there.
+    Poke,
+    /// The address to show in the memory view.
+    Memory,
+    /// A key to hold down for the next step or frame.
+    Key,
+}
+
+impl Prompt {
+    fn label(&self) -> &'static str {
+        match self {
+            Prompt::Poke => "poke ADDRESS BYTE...",
+            Prompt::Memory => "memory address",
+            Prompt::Key => "key 0-F",
+        }
+    }
+}
+
+/// An interactive terminal debugger, drawn with plain text so that it works over SSH.
+pub struct Tui {
+    debugger: crate::debugger::Debugger,
+    /// The disassembly line that breakpoints are toggled on. None follows the program counter.
+    selected: Option<usize>,
+    memory_view: usize,
+    running: bool,
+    prompt: Option<(Prompt, String)>,
+    /// A key pressed for the next step or frame, released after it.
+    held_key: Option<u8>,
+    message: String,
+    quit: bool,
+}
+
+impl Tui {
+    pub fn new(debugger: crate::debugger::Debugger) -> Self {
+        let memory_view = debugger.emulator().config().memory.start_address;
+        Self {
+            debugger,
+            selected: None,
+            memory_view,
+            running: false,
+            prompt: None,
+            held_key: None,
+            message: String::new(),
+            quit: false,
+        }
+    }
+
+    pub fn debugger(&self) -> &crate::debugger::Debugger {
+        &self.debugger
+    }
+
+    /// Whether q was pressed.
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Acts on a key press.
+    pub fn handle_key(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+        if let Some((prompt, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.submit(prompt, &input),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.prompt = Some((prompt, input));
+                }
+                KeyCode::Char(character) => {
+                    input.push(character);
+                    self.prompt = Some((prompt, input));
+                }
+                _ => self.prompt = Some((prompt, input)),
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('c') => {
+                self.running = !self.running;
+                self.message = if self.running { "running" } else { "paused" }.to_string();
+            }
+            // everything else waits for a pause
+            _ if self.running => {}
+            KeyCode::Char('s') => {
+                let reason = match self.debugger.step() {
+                    Err(error) => crate::debugger::StopReason::Error(error),
+                    Ok(outcome) if outcome.exited => crate::debugger::StopReason::Exited,
+                    Ok(outcome) if outcome.waiting_for_key => {
+                        crate::debugger::StopReason::WaitingForKey
+                    }
+                    Ok(_) => crate::debugger::StopReason::Step,
+                };
+                self.stopped(reason);
+            }
+            KeyCode::Char('n') => {
+                let reason = self.debugger.step_over(STEP_OVER_LIMIT);
+                self.stopped(reason);
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let selected = self.selected_address();
+                self.selected = Some(if key.code == KeyCode::Up {
+                    selected.saturating_sub(2)
+                } else {
+                    selected + 2
+                });
+            }
+            KeyCode::Char('b') => {
+                let address = self.selected_address();
+                self.message = if self.debugger.remove_breakpoint(address) {
+                    format!("removed the breakpoint at {:#05X}", address)
+                } else {
+                    self.debugger.add_breakpoint(address);
+                    format!("added a breakpoint at {:#05X}", address)
+                };
+            }
+            KeyCode::Char('m') => self.prompt = Some((Prompt::Poke, String::new())),
+            KeyCode::Char('g') => self.prompt = Some((Prompt::Memory, String::new())),
+            KeyCode::Char('k') => self.prompt = Some((Prompt::Key, String::new())),
+            KeyCode::PageUp => {
+                self.memory_view = self
+                    .memory_view
+                    .saturating_sub(MEMORY_ROWS * MEMORY_ROW_LENGTH)
+            }
+            KeyCode::PageDown => self.memory_view += MEMORY_ROWS * MEMORY_ROW_LENGTH,
+            _ => {}
+        }
+    }
+
+    /// Runs a frame's worth of instructions if the debugger is continuing.
+    pub fn tick(&mut self) {
+        if !self.running {
+            return;
+        }
+        let instructions_per_frame = self.debugger.emulator().config().instructions_per_frame;
+        match self.debugger.run(instructions_per_frame as u64) {
+            crate::debugger::StopReason::CycleLimit => self.release_key(),
+            // keep going, so that the ROM carries on once a key is pressed
+            crate::debugger::StopReason::WaitingForKey => {
+                self.message = "waiting for a key, press k to send one".to_string();
+                self.release_key();
+            }
+            reason => {
+                self.running = false;
+                self.stopped(reason);
+            }
+        }
+    }
+
+    /// The screen as lines of text.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .disassembly()
+            .into_iter()
+            .zip(self.machine_state())
+            .map(|(left, right)| format!("{:<width$}{}", left, right, width = DISASSEMBLY_WIDTH))
+            .collect();
+        lines.push(String::new());
+        lines.extend(self.memory());
+        lines.push(String::new());
+        lines.extend(self.screen());
+        lines.push(self.message.clone());
+        lines.push(match &self.prompt {
+            Some((prompt, input)) => format!("{}: {}", prompt.label(), input),
+            None => HELP.to_string(),
+        });
+        lines
+    }
+
+    fn selected_address(&self) -> usize {
+        self.selected
+            .unwrap_or_else(|| self.debugger.emulator().program_counter())
+    }
+
+    fn stopped(&mut self, reason: crate::debugger::StopReason) {
+        self.release_key();
+        self.selected = None;
+        self.message = describe(&reason);
+    }
+
+    fn release_key(&mut self) {
+        if let Some(key) = self.held_key.take() {
+            let _ = self
+                .debugger
+                .emulator_mut()
+                .set_key(key, winit::event::ElementState::Released);
+        }
+    }
+
+    fn submit(&mut self, prompt: Prompt, input: &str) {
+        let numbers: Option<Vec<usize>> = input
+            .split_whitespace()
+            .map(|number| usize::from_str_radix(number.trim_start_matches("0x"), 16).ok())
+            .collect();
+        self.message = match (prompt, numbers.as_deref()) {
+            (Prompt::Poke, Some([address, bytes @ ..]))
+                if !bytes.is_empty() && bytes.iter().all(|byte| *byte <= 0xFF) =>
+            {
+                let bytes: Vec<u8> = bytes.iter().map(|byte| *byte as u8).collect();
+                match self.debugger.emulator_mut().poke(*address, &bytes) {
+                    Ok(()) => format!("wrote {} bytes at {:#05X}", bytes.len(), address),
+                    Err(error) => error.to_string(),
+                }
+            }
+            (Prompt::Memory, Some([address])) => {
+                self.memory_view = address - address % MEMORY_ROW_LENGTH;
+                String::new()
+            }
+            (Prompt::Key, Some([key])) if *key <= 0xF => {
+                self.release_key();
+                let key = *key as u8;
+                match self
+                    .debugger
+                    .emulator_mut()
+                    .set_key(key, winit::event::ElementState::Pressed)
+                {
+                    Ok(()) => {
+                        self.held_key = Some(key);
+                        format!("holding key {:X} for the next step", key)
+                    }
+                    Err(error) => error.to_string(),
+                }
+            }
+            _ => format!("expected {} in hex", prompt.label()),
+        };
+    }
+
+    /// The instructions around the program counter, marking breakpoints, the program counter and the selection.
+    fn disassembly(&self) -> Vec<String> {
+        let emulator = self.debugger.emulator();
+        let memory = emulator.memory();
+        let program_counter = emulator.program_counter();
+        let selected = self.selected_address();
+        let breakpoints: Vec<usize> = self
+            .debugger
+            .breakpoints()
+            .map(|(address, _)| address)
+            .collect();
+
+        let mut lines = vec!["disassembly".to_string()];
+        let mut address = selected
+            .min(program_counter)
+            .saturating_sub(DISASSEMBLY_BEFORE * 2);
+        while lines.len() < DISASSEMBLY_LINES {
+            let Some(opcode) = memory.get(address..address + 2) else {
+                break;
+            };
+            let decoded = crate::instruction::decode_for(
+                u16::from_be_bytes([opcode[0], opcode[1]]),
+                emulator.config().instruction_set,
+            );
+            let (length, text) = match decoded {
+                Ok(instruction) => {
+                    let length = instruction.length() as usize;
+                    let bytes = memory.get(address..address + length).unwrap_or(opcode);
+                    (
+                        length,
+                        crate::disassembler::mnemonic(
+                            &instruction,
+                            bytes,
+                            crate::disassembler::Syntax::Octo,
+                            None,
+                        ),
+                    )
+                }
+                Err(_) => (2, format!("{:#04X} {:#04X}", opcode[0], opcode[1])),
+            };
+            let bytes: String = memory[address..(address + length).min(memory.len())]
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            lines.push(format!(
+                "{}{}{} {:03X}  {:<8} {}",
+                if breakpoints.contains(&address) {
+                    'B'
+                } else {
+                    ' '
+                },
+                if address == program_counter { '>' } else { ' ' },
+                if address == selected { '*' } else { ' ' },
+                address,
+                bytes,
+                text
+            ));
+            address += length;
+        }
+        lines.resize(DISASSEMBLY_LINES, String::new());
+        lines
+    }
+
+    /// The registers, timers and call stack, as many lines as the disassembly.
+    fn machine_state(&self) -> Vec<String> {
+        let emulator = self.debugger.emulator();
+        let mut lines = vec!["registers".to_string()];
+        for row in emulator.registers().chunks(4).enumerate() {
+            let (row, values) = row;
+            lines.push(
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(column, value)| format!("V{:X} {:02X}", row * 4 + column, value))
+                    .collect::<Vec<String>>()
+                    .join("  "),
+            );
+        }
+        lines.push(format!(
+            "PC {:03X}  I {:04X}",
+            emulator.program_counter(),
+            emulator.index_register()
+        ));
+        lines.push(format!(
+            "DT {:02X}  ST {:02X}  cycles {}",
+            emulator.delay_timer().counter,
+            emulator.sound_timer().counter,
+            self.debugger.cycles()
+        ));
+        lines.push(String::new());
+        lines.push(format!(
+            "stack {}/{}",
+            emulator.stack().len(),
+            emulator.stack().max_depth()
+        ));
+        lines.extend(
+            emulator
+                .stack()
+                .iter()
+                .map(|address| format!("  {:03X}", address)),
+        );
+        lines.resize(DISASSEMBLY_LINES, String::new());
+        lines
+    }
+
+    /// Rows of memory in hex and ASCII from the memory view address.
+    fn memory(&self) -> Vec<String> {
+        let memory = self.debugger.emulator().memory();
+        let mut lines = vec!["memory".to_string()];
+        for row in 0..MEMORY_ROWS {
+            let start = self.memory_view + row * MEMORY_ROW_LENGTH;
+            let Some(bytes) = memory.get(start..(start + MEMORY_ROW_LENGTH).min(memory.len()))
+            else {
+                break;
+            };
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let text: String = bytes
+                .iter()
+                .map(|byte| {
+                    if byte.is_ascii_graphic() {
+                        *byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            lines.push(format!("{:04X}  {:<48} {}", start, hex.join(" "), text));
+        }
+        lines
+    }
+
+    /// The framebuffer in a box, two pixel rows to a line of half blocks.
+    fn screen(&self) -> Vec<String> {
+        let framebuffer = self.debugger.emulator().framebuffer();
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        let lit = |x: usize, y: usize| (y < height) && (framebuffer.planes(x, y) != 0);
+        let mut lines = vec![format!("+{}+", "-".repeat(width))];
+        for y in (0..height).step_by(2) {
+            let row: String = (0..width)
+                .map(|x| match (lit(x, y), lit(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect();
+            lines.push(format!("|{}|", row));
+        }
+        lines.push(format!("+{}+", "-".repeat(width)));
+        lines
+    }
+}
+
+/// A line for the status bar about why running stopped.
+fn describe(reason: &crate::debugger::StopReason) -> String {
+    match reason {
+        crate::debugger::StopReason::Breakpoint { address } => {
+            format!("breakpoint at {:#05X}", address)
+        }
+        crate::debugger::StopReason::Watchpoint { address, access } => {
+            format!("watchpoint: {:?} of {:#05X}", access, address)
+        }
+        crate::debugger::StopReason::Opcode { address, opcode } => {
+            format!("opcode {:04X} at {:#05X}", opcode, address)
+        }
+        crate::debugger::StopReason::Predicate | crate::debugger::StopReason::Step => String::new(),
+        crate::debugger::StopReason::CycleLimit => "still running, gave up".to_string(),
+        crate::debugger::StopReason::WaitingForKey => {
+            "waiting for a key, press k to send one".to_string()
+        }
+        crate::debugger::StopReason::Exited => "the ROM exited".to_string(),
+        crate::debugger::StopReason::Error(error) => error.to_string(),
+    }
+}
+
+/// Restores the terminal when the debugger ends, even if it ends in an error.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            std::io::stdout(),
+            crossterm::cursor::Show,
+            crossterm::terminal::LeaveAlternateScreen
+        );
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Runs the debugger in the terminal until q is pressed.
+pub fn run(debugger: crate::debugger::Debugger) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    crossterm::terminal::enable_raw_mode()?;
+    let _guard = TerminalGuard;
+    crossterm::execute!(
+        stdout,
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::cursor::Hide,
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+    )?;
+
+    let mut tui = Tui::new(debugger);
+    while !tui.has_quit() {
+        for (row, line) in tui.render().iter().enumerate() {
+            crossterm::queue!(
+                stdout,
+                crossterm::cursor::MoveTo(0, row as u16),
+                crossterm::style::Print(line),
+                crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine)
+            )?;
+        }
+        crossterm::queue!(
+            stdout,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::FromCursorDown)
+        )?;
+        std::io::Write::flush(&mut stdout)?;
+
+        if crossterm::event::poll(FRAME_DURATION)? {
+            if let crossterm::event::Event::Key(key) = crossterm::event::read()? {
+                // Windows also reports releases
+                if key.kind == crossterm::event::KeyEventKind::Press {
+                    tui.handle_key(key);
+                }
+            }
+        }
+        tui.tick();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use super::Tui;
+    use crate::debugger::Debugger;
+    use crate::emulator::Emulator;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn press(tui: &mut Tui, keys: &str) {
+        for character in keys.chars() {
+            let code = match character {
+                '\n' => KeyCode::Enter,
+                '^' => KeyCode::Up,
+                'v' => KeyCode::Down,
+                character => KeyCode::Char(character),
+            };
+            tui.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    const SOURCE: &str = "
+        : main
+            v0 := 5
+            v1 := 1
+            draw
+        : end
+            jump end
+        : draw
+            i := hex v0
+            sprite v1 v1 5
+            return
+    ";
+
+    /// Whether any line of the screen starts with the text.
+    fn shows(tui: &Tui, start: &str) -> bool {
+        tui.render().iter().any(|line| line.starts_with(start))
+    }
+
+    /// The message above the help line.
+    fn status(tui: &Tui) -> String {
+        tui.render().iter().rev().nth(1).unwrap().clone()
+    }
+
+    #[test]
+    fn test_render() {
+        let tui = Tui::new(Debugger::new(Emulator::assembled(SOURCE)));
+        assert!(shows(&tui, " >* 200  6005     v0 := 0x05"));
+        assert!(shows(&tui, "    204  2208     :call 0x208"));
+        let screen = tui.render();
+        assert!(screen[1].ends_with("V0 00  V1 00  V2 00  V3 00"));
+        assert!(screen.contains(&format!("+{}+", "-".repeat(64))));
+        assert!(shows(&tui, "0200  60 05 61 01 22 08 12 06"));
+        assert_eq!(screen.last().unwrap(), super::HELP);
+    }
+
+    #[test]
+    fn test_step_over_and_breakpoints() {
+        let mut stepping = Tui::new(Debugger::new(Emulator::assembled(SOURCE)));
+        press(&mut stepping, "ssn");
+        let emulator = stepping.debugger().emulator();
+        assert_eq!(emulator.program_counter(), 0x206);
+        assert_eq!(emulator.stack().len(), 0);
+        assert!(emulator.framebuffer().get(1, 1));
+        // the 5 is drawn in half blocks, two rows to a line
+        assert!(stepping.render().contains(&format!("|{:<64}|", " ▄▄▄▄")));
+
+        // select two lines after the program counter and put a breakpoint there
+        let mut breaking = Tui::new(Debugger::new(Emulator::assembled(SOURCE)));
+        press(&mut breaking, "svvb");
+        assert!(shows(&breaking, "B * 206"));
+        press(&mut breaking, "c");
+        breaking.tick();
+        assert_eq!(status(&breaking), "breakpoint at 0x206");
+        assert!(breaking.debugger().emulator().framebuffer().get(1, 1));
+        press(&mut breaking, "b");
+        assert_eq!(breaking.debugger().breakpoints().count(), 0);
+        press(&mut breaking, "^^^");
+        assert!(shows(&breaking, "  * 200"));
+    }
+
+    #[test]
+    fn test_prompts() {
+        let mut tui = Tui::new(Debugger::new(Emulator::assembled(
+            "v3 := key : end jump end",
+        )));
+        press(&mut tui, "m0300 12 ab\n");
+        assert_eq!(
+            tui.debugger().emulator().memory()[0x300..0x302],
+            [0x12, 0xAB]
+        );
+        press(&mut tui, "g305\n");
+        assert!(shows(&tui, "0300  12 AB"));
+        press(&mut tui, "m300 1ff\n");
+        assert_eq!(status(&tui), "expected poke ADDRESS BYTE... in hex");
+
+        // a held key is let go after the next step, which the VIP waits for
+        press(&mut tui, "s");
+        assert_eq!(status(&tui), "waiting for a key, press k to send one");
+        press(&mut tui, "kf\ns");
+        assert_eq!(tui.debugger().emulator().registers()[3], 0xF);
+        press(&mut tui, "q");
+        assert!(tui.has_quit());
+    }
+}